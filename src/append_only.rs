@@ -0,0 +1,135 @@
+//! An arena that allows inserting values through a shared reference.
+
+use crate::SlabKey;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::marker::PhantomData;
+
+const FIRST_CHUNK_SIZE: usize = 64;
+
+/// Storage for a uniform data type that never removes values.
+///
+/// Values are inserted with a shared reference, so references to values
+/// stored earlier can be held while inserting new ones. Values are stored
+/// in chunks of doubling sizes that are never reallocated.
+#[derive(Debug)]
+pub struct AppendOnlyTypedSlab<K, V> {
+    chunks: RefCell<Vec<Vec<V>>>,
+    len: Cell<usize>,
+    _key: PhantomData<K>,
+}
+
+impl<K, V> Default for AppendOnlyTypedSlab<K, V> {
+    fn default() -> Self {
+        Self {
+            chunks: RefCell::new(Vec::new()),
+            len: Cell::new(0),
+            _key: PhantomData,
+        }
+    }
+}
+
+/// Return the chunk and the offset in the chunk for an index.
+fn locate(idx: usize) -> (usize, usize) {
+    let n = idx / FIRST_CHUNK_SIZE + 1;
+    let chunk = (usize::BITS - 1 - n.leading_zeros()) as usize;
+    let offset = idx - FIRST_CHUNK_SIZE * ((1 << chunk) - 1);
+    (chunk, offset)
+}
+
+impl<K, V> AppendOnlyTypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    /// Construct a new, empty `AppendOnlyTypedSlab`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value in the slab, returning key assigned to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn insert(&self, value: V) -> K {
+        let idx = self.len.get();
+        let key = K::from_index(idx);
+        let (chunk, _) = locate(idx);
+        let mut chunks = self.chunks.borrow_mut();
+        if chunk == chunks.len() {
+            // The chunk never grows over the capacity, so it's never reallocated.
+            chunks.push(Vec::with_capacity(FIRST_CHUNK_SIZE << chunk));
+        }
+        chunks[chunk].push(value);
+        self.len.set(idx + 1);
+        key
+    }
+
+    fn value(&self, idx: usize) -> Option<&V> {
+        let (chunk, offset) = locate(idx);
+        let chunks = self.chunks.borrow();
+        let value: *const V = chunks.get(chunk)?.get(offset)?;
+        // SAFETY: values are never removed or mutated while the slab is shared,
+        // and chunks are never reallocated, so the value lives at the same
+        // address as long as the slab is borrowed.
+        Some(unsafe { &*value })
+    }
+
+    /// Return a reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.value(key.index())
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        let (chunk, offset) = locate(key.index());
+        self.chunks.get_mut().get_mut(chunk)?.get_mut(offset)
+    }
+
+    /// Return true if a value is associated with the given key.
+    pub fn contains(&self, key: K) -> bool {
+        key.index() < self.len.get()
+    }
+
+    /// Return true if there are no values stored in the slab.
+    pub fn is_empty(&self) -> bool {
+        self.len.get() == 0
+    }
+
+    /// Return a number of stored values.
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    /// Return an iterator over the values stored before the call.
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        (0..self.len.get()).filter_map(move |idx| Some((K::from_index(idx), self.value(idx)?)))
+    }
+
+    /// Convert the slab into a vector of values ordered by keys.
+    pub fn into_vec(self) -> Vec<V> {
+        self.chunks.into_inner().into_iter().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_insert_shared() {
+        let arena: AppendOnlyTypedSlab<usize, String> = AppendOnlyTypedSlab::new();
+        let first = arena.insert("first".into());
+        let first_ref = arena.get(first).unwrap();
+        for idx in 1..1000 {
+            arena.insert(format!("{first_ref}-{idx}"));
+        }
+        assert_eq!(first_ref, "first");
+        assert_eq!(arena.get(999).map(String::as_str), Some("first-999"));
+        assert_eq!(arena.iter().count(), 1000);
+        assert_eq!(locate(FIRST_CHUNK_SIZE * 3), (2, 0));
+    }
+}
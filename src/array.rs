@@ -0,0 +1,258 @@
+//! A slab with a fixed capacity that doesn't require an allocator.
+
+use crate::SlabKey;
+use core::marker::PhantomData;
+use core::mem;
+
+#[derive(Debug)]
+enum Slot<V> {
+    Vacant(usize),
+    Occupied(V),
+}
+
+/// Storage for up to `N` values of a uniform data type in an inline array.
+///
+/// Vacant entries of the array are linked into a free list,
+/// so no allocations are ever made.
+#[derive(Debug)]
+pub struct ArrayTypedSlab<K, V, const N: usize> {
+    slots: [Slot<V>; N],
+    next: usize,
+    len: usize,
+    _key: PhantomData<K>,
+}
+
+impl<K, V, const N: usize> Default for ArrayTypedSlab<K, V, N> {
+    fn default() -> Self {
+        Self {
+            slots: core::array::from_fn(|idx| Slot::Vacant(idx + 1)),
+            next: 0,
+            len: 0,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K, V, const N: usize> ArrayTypedSlab<K, V, N>
+where
+    K: SlabKey,
+{
+    /// Construct a new, empty `ArrayTypedSlab`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the number of values the slab can store.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Return true if the slab can't store more values.
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Try to insert a value in the slab, returning key assigned and
+    /// a reference to the stored value.
+    /// If the slab is full, then the value is returned back as an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn try_insert_entry(&mut self, value: V) -> Result<(K, &mut V), V> {
+        let idx = self.next;
+        let Some(slot) = self.slots.get_mut(idx) else {
+            return Err(value);
+        };
+        let key = K::from_index(idx);
+        if let Slot::Vacant(next) = mem::replace(slot, Slot::Occupied(value)) {
+            self.next = next;
+        }
+        self.len += 1;
+        match slot {
+            Slot::Occupied(value) => Ok((key, value)),
+            Slot::Vacant(_) => unreachable!("the value was inserted"),
+        }
+    }
+
+    /// Try to insert a value in the slab, returning key assigned to the value.
+    /// If the slab is full, then the value is returned back as an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn try_insert(&mut self, value: V) -> Result<K, V> {
+        self.try_insert_entry(value).map(|(key, _)| key)
+    }
+
+    /// Insert a value in the slab, returning key assigned to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slab is full or the assigned index can't be represented
+    /// by the key.
+    ///
+    pub fn insert(&mut self, value: V) -> K {
+        match self.try_insert(value) {
+            Ok(key) => key,
+            Err(_) => panic!("the slab is full"),
+        }
+    }
+
+    /// Insert a value in the slab, returning key assigned and a reference
+    /// to the stored value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slab is full or the assigned index can't be represented
+    /// by the key.
+    ///
+    pub fn insert_entry(&mut self, value: V) -> (K, &mut V) {
+        match self.try_insert_entry(value) {
+            Ok(entry) => entry,
+            Err(_) => panic!("the slab is full"),
+        }
+    }
+
+    /// Remove and return the value associated with the given key.
+    /// The key is then released and may be associated with future stored values.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let idx = key.index();
+        let slot = self.slots.get_mut(idx)?;
+        if let Slot::Vacant(_) = slot {
+            return None;
+        }
+        let value = mem::replace(slot, Slot::Vacant(self.next));
+        self.next = idx;
+        self.len -= 1;
+        match value {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant(_) => None,
+        }
+    }
+
+    /// Return a reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get(&self, key: K) -> Option<&V> {
+        match self.slots.get(key.index())? {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant(_) => None,
+        }
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        match self.slots.get_mut(key.index())? {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant(_) => None,
+        }
+    }
+
+    /// Return true if a value is associated with the given key.
+    pub fn contains(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Return true if there are no values stored in the slab.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return a number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return an iterator over the slab.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (K, &V)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, slot)| match slot {
+                Slot::Occupied(value) => Some((K::from_index(idx), value)),
+                Slot::Vacant(_) => None,
+            })
+    }
+
+    /// Return an iterator that allows modifying each value.
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = (K, &mut V)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(idx, slot)| match slot {
+                Slot::Occupied(value) => Some((K::from_index(idx), value)),
+                Slot::Vacant(_) => None,
+            })
+    }
+
+    /// Return an iterator with references to values.
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// Return a draining iterator that removes all elements from the slab
+    /// and yields the removed items.
+    pub fn drain(&mut self) -> impl DoubleEndedIterator<Item = V> + '_ {
+        self.next = 0;
+        self.len = 0;
+        Drain {
+            slots: self.slots.iter_mut().enumerate(),
+        }
+    }
+}
+
+/// Removes the remaining values when dropped and relinks the free list
+/// in the order of indices.
+struct Drain<'a, V> {
+    slots: core::iter::Enumerate<core::slice::IterMut<'a, Slot<V>>>,
+}
+
+fn take<V>((idx, slot): (usize, &mut Slot<V>)) -> Option<V> {
+    match mem::replace(slot, Slot::Vacant(idx + 1)) {
+        Slot::Occupied(value) => Some(value),
+        Slot::Vacant(_) => None,
+    }
+}
+
+impl<V> Iterator for Drain<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        self.slots.by_ref().find_map(take)
+    }
+}
+
+impl<V> DoubleEndedIterator for Drain<'_, V> {
+    fn next_back(&mut self) -> Option<V> {
+        self.slots.by_ref().rev().find_map(take)
+    }
+}
+
+impl<V> Drop for Drain<'_, V> {
+    fn drop(&mut self) {
+        self.slots.by_ref().for_each(|entry| drop(take(entry)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fixed_capacity() {
+        let mut slab: ArrayTypedSlab<usize, &str, 2> = ArrayTypedSlab::new();
+        let first = slab.insert("first");
+        slab.insert("second");
+        assert_eq!(slab.try_insert("third"), Err("third"));
+        assert_eq!(slab.remove(first), Some("first"));
+        assert_eq!(slab.try_insert("third"), Ok(first));
+        assert_eq!(slab.drain().next_back(), Some("second"));
+        assert!(slab.is_empty());
+        assert_eq!(slab.insert("again"), 0);
+        assert_eq!(slab.insert("more"), 1);
+    }
+}
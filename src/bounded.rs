@@ -0,0 +1,154 @@
+//! A slab with a hard limit of stored values.
+
+use crate::{Drain, Iter, IterMut, SlabKey, TypedSlab};
+
+/// A `TypedSlab` that refuses to store more than `max_len` values.
+#[derive(Debug)]
+pub struct BoundedTypedSlab<K, V> {
+    slab: TypedSlab<K, V>,
+    max_len: usize,
+}
+
+impl<K, V> BoundedTypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    /// Construct a new, empty `BoundedTypedSlab` that stores
+    /// at most `max_len` values.
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            slab: TypedSlab::new(),
+            max_len,
+        }
+    }
+
+    /// Construct a new, empty `BoundedTypedSlab` with memory
+    /// allocated for all `max_len` values.
+    pub fn preallocated(max_len: usize) -> Self {
+        Self {
+            slab: TypedSlab::with_capacity(max_len),
+            max_len,
+        }
+    }
+
+    /// Return a reference to the underlying `TypedSlab`.
+    pub fn as_typed(&self) -> &TypedSlab<K, V> {
+        &self.slab
+    }
+
+    /// Return the maximal number of values the slab can store.
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    /// Return true if the slab can't store more values.
+    pub fn is_full(&self) -> bool {
+        self.slab.len() >= self.max_len
+    }
+
+    /// Try to insert a value in the slab, returning key assigned to the value.
+    /// If the slab is full, then the value is returned back as an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn try_insert(&mut self, value: V) -> Result<K, V> {
+        if self.is_full() {
+            Err(value)
+        } else {
+            Ok(self.slab.insert(value))
+        }
+    }
+
+    /// Try to insert a value in the slab, returning key assigned and
+    /// a reference to the stored value.
+    /// If the slab is full, then the value is returned back as an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn try_insert_entry(&mut self, value: V) -> Result<(K, &mut V), V> {
+        if self.is_full() {
+            Err(value)
+        } else {
+            Ok(self.slab.insert_entry(value))
+        }
+    }
+
+    /// Remove and return the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        self.slab.remove(key)
+    }
+
+    /// Return a reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.slab.get(key)
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.slab.get_mut(key)
+    }
+
+    /// Return true if a value is associated with the given key.
+    pub fn contains(&self, key: K) -> bool {
+        self.slab.contains(key)
+    }
+
+    /// Return true if there are no values stored in the slab.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Return a number of stored values.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return the number of values the slab can store without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Shrink the capacity of the slab as much as possible
+    /// without invalidating keys.
+    pub fn shrink_to_fit(&mut self) {
+        self.slab.shrink_to_fit();
+    }
+
+    /// Return an iterator over the slab.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.slab.iter()
+    }
+
+    /// Return an iterator that allows modifying each value.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        self.slab.iter_mut()
+    }
+
+    /// Return a draining iterator that removes all elements from the slab
+    /// and yields the removed items.
+    pub fn drain(&mut self) -> Drain<'_, V> {
+        self.slab.drain()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_full() {
+        let mut slab: BoundedTypedSlab<usize, &str> = BoundedTypedSlab::new(2);
+        let first = slab.try_insert("first").unwrap();
+        slab.try_insert("second").unwrap();
+        assert_eq!(slab.try_insert("third"), Err("third"));
+        slab.remove(first);
+        assert_eq!(slab.try_insert("third"), Ok(first));
+    }
+}
@@ -0,0 +1,206 @@
+//! A slab that rejects keys issued by other slabs.
+
+use crate::{SlabKey, TypedSlab};
+
+/// A unique identifier of a slab instance.
+///
+/// It's a zero-sized type and never differs if the `branding` feature
+/// is disabled, so checks of brands are optimized out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Brand {
+    #[cfg(feature = "branding")]
+    id: usize,
+}
+
+impl Brand {
+    fn unique() -> Self {
+        #[cfg(feature = "branding")]
+        {
+            use core::sync::atomic::{AtomicUsize, Ordering};
+
+            static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+            Self {
+                id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            }
+        }
+        #[cfg(not(feature = "branding"))]
+        {
+            Self {}
+        }
+    }
+}
+
+/// A key issued by [`BrandedTypedSlab`] that carries the brand
+/// of the slab instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BrandedKey<K> {
+    key: K,
+    brand: Brand,
+}
+
+impl<K> BrandedKey<K> {
+    /// Return the key without the brand.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+/// A `TypedSlab` that embeds a unique brand of the instance into its keys
+/// and rejects keys issued by other instances.
+///
+/// Brands are checked only if the `branding` feature is enabled,
+/// otherwise keys have the same size as `K` and checks are free.
+#[derive(Debug)]
+pub struct BrandedTypedSlab<K, V> {
+    slab: TypedSlab<K, V>,
+    brand: Brand,
+}
+
+impl<K, V> Default for BrandedTypedSlab<K, V> {
+    fn default() -> Self {
+        Self {
+            slab: TypedSlab::default(),
+            brand: Brand::unique(),
+        }
+    }
+}
+
+impl<K, V> BrandedTypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    /// Construct a new, empty `BrandedTypedSlab` with a unique brand.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn brand(&self, key: K) -> BrandedKey<K> {
+        BrandedKey {
+            key,
+            brand: self.brand,
+        }
+    }
+
+    fn unbrand(&self, key: BrandedKey<K>) -> Option<K> {
+        if key.brand == self.brand {
+            Some(key.key)
+        } else {
+            None
+        }
+    }
+
+    /// Return true if the key was issued by this slab.
+    pub fn owns(&self, key: &BrandedKey<K>) -> bool {
+        key.brand == self.brand
+    }
+
+    /// Insert a value in the slab, returning key assigned to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn insert(&mut self, value: V) -> BrandedKey<K> {
+        let key = self.slab.insert(value);
+        self.brand(key)
+    }
+
+    /// Insert a value in the slab, returning key assigned and a reference
+    /// to the stored value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn insert_entry(&mut self, value: V) -> (BrandedKey<K>, &mut V) {
+        let brand = self.brand;
+        let (key, value) = self.slab.insert_entry(value);
+        (BrandedKey { key, brand }, value)
+    }
+
+    /// Remove and return the value associated with the given key.
+    /// If the key was issued by another slab or is not associated
+    /// with a value, then `None` is returned.
+    pub fn remove(&mut self, key: BrandedKey<K>) -> Option<V> {
+        let key = self.unbrand(key)?;
+        self.slab.remove(key)
+    }
+
+    /// Return a reference to the value associated with the given key.
+    /// If the key was issued by another slab or is not associated
+    /// with a value, then `None` is returned.
+    pub fn get(&self, key: BrandedKey<K>) -> Option<&V> {
+        let key = self.unbrand(key)?;
+        self.slab.get(key)
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    /// If the key was issued by another slab or is not associated
+    /// with a value, then `None` is returned.
+    pub fn get_mut(&mut self, key: BrandedKey<K>) -> Option<&mut V> {
+        let key = self.unbrand(key)?;
+        self.slab.get_mut(key)
+    }
+
+    /// Return true if the key was issued by this slab and is associated
+    /// with a value.
+    pub fn contains(&self, key: BrandedKey<K>) -> bool {
+        self.unbrand(key).is_some_and(|key| self.slab.contains(key))
+    }
+
+    /// Return true if there are no values stored in the slab.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Return a number of stored values.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    /// Return an iterator over the slab.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (BrandedKey<K>, &V)> {
+        let brand = self.brand;
+        self.slab
+            .iter()
+            .map(move |(key, v)| (BrandedKey { key, brand }, v))
+    }
+
+    /// Return an iterator that allows modifying each value.
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = (BrandedKey<K>, &mut V)> {
+        let brand = self.brand;
+        self.slab
+            .iter_mut()
+            .map(move |(key, v)| (BrandedKey { key, brand }, v))
+    }
+
+    /// Return a reference to the underlying `TypedSlab`.
+    pub fn as_typed(&self) -> &TypedSlab<K, V> {
+        &self.slab
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_foreign_key() {
+        let mut first: BrandedTypedSlab<usize, &str> = BrandedTypedSlab::new();
+        let mut second: BrandedTypedSlab<usize, &str> = BrandedTypedSlab::new();
+        let key = first.insert("first");
+        second.insert("second");
+        assert_eq!(first.get(key), Some(&"first"));
+        if cfg!(feature = "branding") {
+            assert!(!second.owns(&key));
+            assert_eq!(second.get(key), None);
+            assert_eq!(second.remove(key), None);
+            assert_eq!(second.len(), 1);
+        } else {
+            assert_eq!(
+                core::mem::size_of::<BrandedKey<usize>>(),
+                core::mem::size_of::<usize>()
+            );
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! A slab that can be shared between threads.
+
+use crate::{RawSlab, SlabKey};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+const DEFAULT_SHARDS: usize = 16;
+
+/// Storage for a uniform data type that can be shared between threads.
+///
+/// Values are distributed over shards that are locked independently,
+/// so operations on different shards don't block each other.
+/// The index of a key is interleaved: `local_index * shards + shard`.
+#[derive(Debug)]
+pub struct ConcurrentTypedSlab<K, V> {
+    shards: Box<[RwLock<RawSlab<V>>]>,
+    next_shard: AtomicUsize,
+    _key: PhantomData<K>,
+}
+
+impl<K, V> Default for ConcurrentTypedSlab<K, V> {
+    fn default() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+}
+
+impl<K, V> ConcurrentTypedSlab<K, V> {
+    /// Construct a new, empty `ConcurrentTypedSlab` with the specified
+    /// number of shards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    ///
+    pub fn with_shards(shards: usize) -> Self {
+        assert!(shards > 0, "number of shards must be positive");
+        Self {
+            shards: (0..shards).map(|_| RwLock::new(RawSlab::new())).collect(),
+            next_shard: AtomicUsize::new(0),
+            _key: PhantomData,
+        }
+    }
+
+    fn read(&self, shard: usize) -> RwLockReadGuard<'_, RawSlab<V>> {
+        self.shards[shard]
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self, shard: usize) -> RwLockWriteGuard<'_, RawSlab<V>> {
+        self.shards[shard]
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K, V> ConcurrentTypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    /// Construct a new, empty `ConcurrentTypedSlab`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn locate(&self, key: K) -> (usize, usize) {
+        let idx = key.index();
+        let shards = self.shards.len();
+        (idx % shards, idx / shards)
+    }
+
+    /// Insert a value in the slab, returning key assigned to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn insert(&self, value: V) -> K {
+        let shards = self.shards.len();
+        let shard = self.next_shard.fetch_add(1, Ordering::Relaxed) % shards;
+        let mut slab = self.write(shard);
+        let key = K::from_index(slab.vacant_key() * shards + shard);
+        slab.insert(value);
+        key
+    }
+
+    /// Remove and return the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn remove(&self, key: K) -> Option<V> {
+        let (shard, local) = self.locate(key);
+        self.write(shard).try_remove(local)
+    }
+
+    /// Return a guard with a reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    ///
+    /// The shard of the value can't be modified while the guard is alive.
+    pub fn get(&self, key: K) -> Option<ReadGuard<'_, V>> {
+        let (shard, local) = self.locate(key);
+        let slab = self.read(shard);
+        if slab.contains(local) {
+            Some(ReadGuard { slab, local })
+        } else {
+            None
+        }
+    }
+
+    /// Return a guard with a mutable reference to the value associated
+    /// with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    ///
+    /// The shard of the value is locked exclusively while the guard is alive.
+    pub fn get_mut(&self, key: K) -> Option<WriteGuard<'_, V>> {
+        let (shard, local) = self.locate(key);
+        let slab = self.write(shard);
+        if slab.contains(local) {
+            Some(WriteGuard { slab, local })
+        } else {
+            None
+        }
+    }
+
+    /// Return true if a value is associated with the given key.
+    pub fn contains(&self, key: K) -> bool {
+        let (shard, local) = self.locate(key);
+        self.read(shard).contains(local)
+    }
+
+    /// Return true if there are no values stored in the slab.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return a number of stored values.
+    ///
+    /// Shards are visited one by one, so the result may be outdated
+    /// if the slab is modified concurrently.
+    pub fn len(&self) -> usize {
+        (0..self.shards.len())
+            .map(|shard| self.read(shard).len())
+            .sum()
+    }
+}
+
+/// A guard that provides shared access to a value of [`ConcurrentTypedSlab`].
+#[derive(Debug)]
+pub struct ReadGuard<'a, V> {
+    slab: RwLockReadGuard<'a, RawSlab<V>>,
+    local: usize,
+}
+
+impl<V> Deref for ReadGuard<'_, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.slab[self.local]
+    }
+}
+
+/// A guard that provides exclusive access to a value of [`ConcurrentTypedSlab`].
+#[derive(Debug)]
+pub struct WriteGuard<'a, V> {
+    slab: RwLockWriteGuard<'a, RawSlab<V>>,
+    local: usize,
+}
+
+impl<V> Deref for WriteGuard<'_, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.slab[self.local]
+    }
+}
+
+impl<V> DerefMut for WriteGuard<'_, V> {
+    fn deref_mut(&mut self) -> &mut V {
+        &mut self.slab[self.local]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_threads() {
+        let slab: Arc<ConcurrentTypedSlab<usize, usize>> = Arc::new(ConcurrentTypedSlab::new());
+        let handles: Vec<_> = (0..4)
+            .map(|n| {
+                let slab = slab.clone();
+                thread::spawn(move || {
+                    let keys: Vec<usize> = (0..100).map(|i| slab.insert(n * 100 + i)).collect();
+                    for (i, key) in keys.into_iter().enumerate() {
+                        assert_eq!(*slab.get(key).unwrap(), n * 100 + i);
+                        *slab.get_mut(key).unwrap() += 1;
+                        assert_eq!(slab.remove(key), Some(n * 100 + i + 1));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert!(slab.is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+//! Typed entries of a slab.
+
+use crate::{RawSlab, RawVacantEntry, SlabKey};
+use core::marker::PhantomData;
+
+/// A handle to a vacant entry in a `TypedSlab`.
+///
+/// The key of the entry is known before the value is inserted,
+/// so the value can store its own key.
+#[derive(Debug)]
+pub struct TypedVacantEntry<'a, K, V> {
+    entry: RawVacantEntry<'a, V>,
+    _key: PhantomData<K>,
+}
+
+impl<'a, K, V> TypedVacantEntry<'a, K, V>
+where
+    K: SlabKey,
+{
+    pub(crate) fn new(entry: RawVacantEntry<'a, V>) -> Self {
+        Self {
+            entry,
+            _key: PhantomData,
+        }
+    }
+
+    /// Return the key that will be associated with the inserted value.
+    pub fn key(&self) -> K {
+        K::from_index(self.entry.key())
+    }
+
+    /// Insert a value in the entry, returning a reference to the stored value.
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.entry.insert(value)
+    }
+}
+
+/// A handle to an occupied entry in a `TypedSlab`.
+#[derive(Debug)]
+pub struct TypedOccupiedEntry<'a, K, V> {
+    slab: &'a mut RawSlab<V>,
+    idx: usize,
+    _key: PhantomData<K>,
+}
+
+impl<'a, K, V> TypedOccupiedEntry<'a, K, V>
+where
+    K: SlabKey,
+{
+    /// Return the key of the entry.
+    pub fn key(&self) -> K {
+        K::from_index(self.idx)
+    }
+
+    /// Return a reference to the value of the entry.
+    pub fn get(&self) -> &V {
+        &self.slab[self.idx]
+    }
+
+    /// Return a mutable reference to the value of the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.slab[self.idx]
+    }
+
+    /// Convert the entry into a mutable reference to its value.
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.slab[self.idx]
+    }
+
+    /// Replace the value of the entry, returning the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    /// Remove the value of the entry from the slab and return it.
+    pub fn remove(self) -> V {
+        self.slab.remove(self.idx)
+    }
+}
+
+/// A handle to an entry of a `TypedSlab` that is not associated
+/// with a value, returned by [`TypedSlab::entry`](crate::TypedSlab::entry).
+#[derive(Debug)]
+pub struct TypedVacantKeyEntry<'a, K, V> {
+    slab: &'a mut RawSlab<V>,
+    idx: usize,
+    _key: PhantomData<K>,
+}
+
+impl<'a, K, V> TypedVacantKeyEntry<'a, K, V>
+where
+    K: SlabKey,
+{
+    /// Return the key of the entry.
+    pub fn key(&self) -> K {
+        K::from_index(self.idx)
+    }
+
+    /// Insert a value in the entry, returning a reference to the stored value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is reserved or the slab can't grow to contain it.
+    ///
+    pub fn insert(self, value: V) -> &'a mut V {
+        if let Err(err) = self.slab.insert_at(self.idx, value) {
+            panic!("{}", err);
+        }
+        &mut self.slab[self.idx]
+    }
+}
+
+/// A view into a single entry of a `TypedSlab`.
+#[derive(Debug)]
+pub enum TypedEntry<'a, K, V> {
+    /// The key is associated with a value.
+    Occupied(TypedOccupiedEntry<'a, K, V>),
+    /// The key is not associated with a value.
+    Vacant(TypedVacantKeyEntry<'a, K, V>),
+}
+
+impl<'a, K, V> TypedEntry<'a, K, V>
+where
+    K: SlabKey,
+{
+    pub(crate) fn new(slab: &'a mut RawSlab<V>, key: K) -> Self {
+        let idx = key.index();
+        if slab.contains(idx) {
+            Self::Occupied(TypedOccupiedEntry {
+                slab,
+                idx,
+                _key: PhantomData,
+            })
+        } else {
+            Self::Vacant(TypedVacantKeyEntry {
+                slab,
+                idx,
+                _key: PhantomData,
+            })
+        }
+    }
+
+    /// Return a reference to the value of the entry,
+    /// inserting the default value if the entry is vacant.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is reserved or the slab can't grow to contain it.
+    ///
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Return a reference to the value of the entry,
+    /// inserting the result of the function if the entry is vacant.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is reserved or the slab can't grow to contain it.
+    ///
+    pub fn or_insert_with<F>(self, f: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert(f()),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! A slab with generational keys that detect stale handles.
+
+use crate::{RawSlab, SlabKey};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// A key issued by [`GenTypedSlab`] that carries the slot index
+/// together with the generation of the slot at the time of insertion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GenKey<K> {
+    key: K,
+    generation: u32,
+}
+
+impl<K> GenKey<K> {
+    /// Return the index part of the key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Return the generation part of the key.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Pre-allocated storage for a uniform data type with generational keys.
+///
+/// Every slot has a generation counter that is bumped when the value
+/// is removed, so keys that refer to removed values never match values
+/// inserted later into the same slot. A slot whose generation is exhausted
+/// is retired and never reused.
+#[derive(Debug)]
+pub struct GenTypedSlab<K, V> {
+    slab: RawSlab<V>,
+    generations: Vec<u32>,
+    _key: PhantomData<K>,
+}
+
+impl<K, V> Default for GenTypedSlab<K, V> {
+    fn default() -> Self {
+        Self {
+            slab: RawSlab::default(),
+            generations: Vec::new(),
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K, V> GenTypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    /// Construct a new, empty `GenTypedSlab`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn make_key(&mut self, idx: usize) -> GenKey<K> {
+        let key = K::from_index(idx);
+        if idx == self.generations.len() {
+            self.generations.push(0);
+        }
+        GenKey {
+            key,
+            generation: self.generations[idx],
+        }
+    }
+
+    /// Remove the value of the slot with a new generation,
+    /// or retire the slot if the generation is exhausted.
+    fn release(&mut self, idx: usize) -> Option<V> {
+        if !self.slab.contains(idx) {
+            return None;
+        }
+        match self.generations[idx].checked_add(1) {
+            Some(generation) => {
+                self.generations[idx] = generation;
+                self.slab.try_remove(idx)
+            }
+            None => self.slab.retire(idx),
+        }
+    }
+
+    fn live_index(&self, key: GenKey<K>) -> Option<usize> {
+        let idx = key.key.index();
+        let generation = *self.generations.get(idx)?;
+        if generation == key.generation && self.slab.contains(idx) {
+            Some(idx)
+        } else {
+            None
+        }
+    }
+
+    /// Insert a value in the slab, returning key assigned to the value.
+    pub fn insert(&mut self, value: V) -> GenKey<K> {
+        let key = self.make_key(self.slab.vacant_key());
+        self.slab.insert(value);
+        key
+    }
+
+    /// Insert a value in the slab, returning key assigned and a reference
+    /// to the stored value.
+    pub fn insert_entry(&mut self, value: V) -> (GenKey<K>, &mut V) {
+        let key = self.make_key(self.slab.vacant_key());
+        let idx = self.slab.insert(value);
+        (key, &mut self.slab[idx])
+    }
+
+    /// Remove and return the value associated with the given key.
+    /// The slot is then released with a new generation, so the key
+    /// won't match values stored in the slot later.
+    ///
+    /// Returns `None` if the key is stale or not associated with a value.
+    pub fn remove(&mut self, key: GenKey<K>) -> Option<V> {
+        let idx = self.live_index(key)?;
+        self.release(idx)
+    }
+
+    /// Return a reference to the value associated with the given key.
+    /// If the key is stale or not associated with a value, then `None` is returned.
+    pub fn get(&self, key: GenKey<K>) -> Option<&V> {
+        let idx = self.live_index(key)?;
+        self.slab.get(idx)
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    /// If the key is stale or not associated with a value, then `None` is returned.
+    pub fn get_mut(&mut self, key: GenKey<K>) -> Option<&mut V> {
+        let idx = self.live_index(key)?;
+        self.slab.get_mut(idx)
+    }
+
+    /// Return true if the key is associated with a value.
+    pub fn contains(&self, key: GenKey<K>) -> bool {
+        self.live_index(key).is_some()
+    }
+
+    /// Return true if there are no values stored in the slab.
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Return an iterator over the slab.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (GenKey<K>, &V)> {
+        let generations = &self.generations;
+        self.slab.iter().map(move |(idx, v)| {
+            let key = GenKey {
+                key: K::from_index(idx),
+                generation: generations[idx],
+            };
+            (key, v)
+        })
+    }
+
+    /// Return an iterator that allows modifying each value.
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = (GenKey<K>, &mut V)> {
+        let generations = &self.generations;
+        self.slab.iter_mut().map(move |(idx, v)| {
+            let key = GenKey {
+                key: K::from_index(idx),
+                generation: generations[idx],
+            };
+            (key, v)
+        })
+    }
+
+    /// Return an iterator with references to values.
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> {
+        self.slab.iter().map(|(_, v)| v)
+    }
+
+    /// Return a draining iterator that removes all elements from the slab
+    /// and yields the removed items. All issued keys become stale.
+    pub fn drain(&mut self) -> impl DoubleEndedIterator<Item = V> + '_ {
+        // Values are removed one by one, so retired slots stay out of use.
+        let values: Vec<V> = (0..self.generations.len())
+            .filter_map(|idx| self.release(idx))
+            .collect();
+        values.into_iter()
+    }
+
+    /// Return a number of stored values.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stale_key() {
+        let mut slab: GenTypedSlab<usize, &str> = GenTypedSlab::new();
+        let old = slab.insert("old");
+        assert_eq!(slab.remove(old), Some("old"));
+        let new = slab.insert("new");
+        assert_eq!(old.key(), new.key());
+        assert_eq!(slab.get(old), None);
+        assert_eq!(slab.remove(old), None);
+        assert_eq!(slab.get(new), Some(&"new"));
+    }
+
+    #[test]
+    fn test_retired_slot() {
+        let mut slab: GenTypedSlab<usize, &str> = GenTypedSlab::new();
+        let first = slab.insert("first");
+        slab.remove(first);
+        slab.generations[0] = u32::MAX;
+        let last = slab.insert("last");
+        assert_eq!(last.generation(), u32::MAX);
+        assert_eq!(slab.remove(last), Some("last"));
+        let next = slab.insert("next");
+        assert_ne!(next.key(), last.key());
+        assert_eq!(slab.drain().collect::<Vec<_>>(), ["next"]);
+        assert_ne!(slab.insert("again").key(), last.key());
+        assert_eq!(slab.get(last), None);
+    }
+}
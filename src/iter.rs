@@ -0,0 +1,389 @@
+//! Iterators over typed slabs.
+
+use crate::{RawDrain, RawIntoIter, RawIter, RawIterMut, RawSlab, SlabKey};
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+/// An iterator over the keys and values of a `TypedSlab`.
+pub struct Iter<'a, K, V> {
+    inner: RawIter<'a, V>,
+    _key: PhantomData<K>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    pub(crate) fn new(inner: RawIter<'a, V>) -> Self {
+        Self {
+            inner,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<'a, K: SlabKey, V> Iterator for Iter<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, value) = self.inner.next()?;
+        Some((K::from_index(idx), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: SlabKey, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (idx, value) = self.inner.next_back()?;
+        Some((K::from_index(idx), value))
+    }
+}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone())
+    }
+}
+
+impl<K: SlabKey, V> ExactSizeIterator for Iter<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K: SlabKey, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V: fmt::Debug> fmt::Debug for Iter<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Iter").field(&self.inner).finish()
+    }
+}
+
+/// A mutable iterator over the keys and values of a `TypedSlab`.
+pub struct IterMut<'a, K, V> {
+    inner: RawIterMut<'a, V>,
+    _key: PhantomData<K>,
+}
+
+impl<'a, K, V> IterMut<'a, K, V> {
+    pub(crate) fn new(inner: RawIterMut<'a, V>) -> Self {
+        Self {
+            inner,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<'a, K: SlabKey, V> Iterator for IterMut<'a, K, V> {
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, value) = self.inner.next()?;
+        Some((K::from_index(idx), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: SlabKey, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (idx, value) = self.inner.next_back()?;
+        Some((K::from_index(idx), value))
+    }
+}
+
+impl<K: SlabKey, V> ExactSizeIterator for IterMut<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K: SlabKey, V> FusedIterator for IterMut<'_, K, V> {}
+
+impl<K, V: fmt::Debug> fmt::Debug for IterMut<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IterMut").field(&self.inner).finish()
+    }
+}
+
+/// An owning iterator over the keys and values of a `TypedSlab`.
+pub struct IntoIter<K, V> {
+    inner: RawIntoIter<V>,
+    _key: PhantomData<K>,
+}
+
+impl<K, V> IntoIter<K, V> {
+    pub(crate) fn new(inner: RawIntoIter<V>) -> Self {
+        Self {
+            inner,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K: SlabKey, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, value) = self.inner.next()?;
+        Some((K::from_index(idx), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: SlabKey, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (idx, value) = self.inner.next_back()?;
+        Some((K::from_index(idx), value))
+    }
+}
+
+impl<K: SlabKey, V> ExactSizeIterator for IntoIter<K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K: SlabKey, V> FusedIterator for IntoIter<K, V> {}
+
+impl<K, V: fmt::Debug> fmt::Debug for IntoIter<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.inner).finish()
+    }
+}
+
+/// An iterator over the keys of a `TypedSlab`.
+pub struct Keys<'a, K, V> {
+    inner: RawIter<'a, V>,
+    _key: PhantomData<K>,
+}
+
+impl<'a, K, V> Keys<'a, K, V> {
+    pub(crate) fn new(inner: RawIter<'a, V>) -> Self {
+        Self {
+            inner,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K: SlabKey, V> Iterator for Keys<'_, K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let (idx, _) = self.inner.next()?;
+        Some(K::from_index(idx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: SlabKey, V> DoubleEndedIterator for Keys<'_, K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        let (idx, _) = self.inner.next_back()?;
+        Some(K::from_index(idx))
+    }
+}
+
+impl<K: SlabKey, V> ExactSizeIterator for Keys<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K: SlabKey, V> FusedIterator for Keys<'_, K, V> {}
+
+impl<K, V> Clone for Keys<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone())
+    }
+}
+
+impl<K, V: fmt::Debug> fmt::Debug for Keys<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Keys").field(&self.inner).finish()
+    }
+}
+
+/// An iterator over the values of a `TypedSlab`.
+#[derive(Debug, Clone)]
+pub struct Values<'a, V> {
+    inner: RawIter<'a, V>,
+}
+
+impl<'a, V> Values<'a, V> {
+    pub(crate) fn new(inner: RawIter<'a, V>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, V> Iterator for Values<'a, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for Values<'_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<V> ExactSizeIterator for Values<'_, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<V> FusedIterator for Values<'_, V> {}
+
+/// A mutable iterator over the values of a `TypedSlab`.
+#[derive(Debug)]
+pub struct ValuesMut<'a, V> {
+    inner: RawIterMut<'a, V>,
+}
+
+impl<'a, V> ValuesMut<'a, V> {
+    pub(crate) fn new(inner: RawIterMut<'a, V>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, V> Iterator for ValuesMut<'a, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for ValuesMut<'_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<V> ExactSizeIterator for ValuesMut<'_, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<V> FusedIterator for ValuesMut<'_, V> {}
+
+/// A draining iterator over the values of a `TypedSlab`.
+#[derive(Debug)]
+pub struct Drain<'a, V> {
+    inner: RawDrain<'a, V>,
+}
+
+impl<'a, V> Drain<'a, V> {
+    pub(crate) fn new(inner: RawDrain<'a, V>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<V> Iterator for Drain<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for Drain<'_, V> {
+    fn next_back(&mut self) -> Option<V> {
+        self.inner.next_back()
+    }
+}
+
+impl<V> ExactSizeIterator for Drain<'_, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<V> FusedIterator for Drain<'_, V> {}
+
+/// An iterator that removes values matching a predicate from a slab
+/// and yields them with their keys.
+///
+/// Values that are not visited because the iterator was dropped
+/// remain in the slab.
+pub struct ExtractIf<'a, K, V, F> {
+    slab: &'a mut RawSlab<V>,
+    idx: usize,
+    end: usize,
+    pred: F,
+    _key: PhantomData<K>,
+}
+
+impl<'a, K, V, F> ExtractIf<'a, K, V, F> {
+    pub(crate) fn new(slab: &'a mut RawSlab<V>, pred: F) -> Self {
+        let end = slab.iter().next_back().map_or(0, |(idx, _)| idx + 1);
+        Self {
+            slab,
+            idx: 0,
+            end,
+            pred,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K, V, F> Iterator for ExtractIf<'_, K, V, F>
+where
+    K: SlabKey,
+    F: FnMut(K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.idx < self.end {
+            let idx = self.idx;
+            self.idx += 1;
+            if let Some(value) = self.slab.get_mut(idx) {
+                if (self.pred)(K::from_index(idx), value) {
+                    let value = self.slab.remove(idx);
+                    return Some((K::from_index(idx), value));
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.idx))
+    }
+}
+
+impl<K, V, F> fmt::Debug for ExtractIf<'_, K, V, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtractIf")
+            .field("idx", &self.idx)
+            .field("end", &self.end)
+            .finish()
+    }
+}
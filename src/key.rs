@@ -0,0 +1,57 @@
+//! Conversions between keys and slab indices.
+
+use core::fmt;
+
+/// A type that can be used as a key of a slab.
+///
+/// The trait is implemented for all types that are convertible
+/// from and into `usize`. Narrow keys (e.g. backed by `u32` or `u16`)
+/// implement it directly to reject indices that don't fit.
+pub trait SlabKey: Sized {
+    /// Try to construct a key from a slab index.
+    fn try_from_index(index: usize) -> Result<Self, IndexOverflow>;
+
+    /// Return the slab index of the key.
+    fn index(&self) -> usize;
+
+    /// Construct a key from a slab index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index can't be represented by the key.
+    ///
+    fn from_index(index: usize) -> Self {
+        match Self::try_from_index(index) {
+            Ok(key) => key,
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
+
+impl<T> SlabKey for T
+where
+    T: From<usize> + Into<usize> + Clone,
+{
+    fn try_from_index(index: usize) -> Result<Self, IndexOverflow> {
+        Ok(T::from(index))
+    }
+
+    fn index(&self) -> usize {
+        self.clone().into()
+    }
+}
+
+/// The error returned when a slab index doesn't fit into a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexOverflow {
+    /// The index that was rejected.
+    pub index: usize,
+}
+
+impl fmt::Display for IndexOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "slab index {} overflows the key type", self.index)
+    }
+}
+
+impl core::error::Error for IndexOverflow {}
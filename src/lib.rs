@@ -1,122 +1,657 @@
-//! A crate with a typed wrapper for [`Slab`].
+//! A crate with typed slabs modeled after [`slab`](https://docs.rs/slab).
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(missing_docs)]
 
-pub use slab::Slab;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+extern crate self as typed_slab;
 
+#[cfg(feature = "alloc")]
+mod append_only;
+mod array;
+#[cfg(feature = "alloc")]
+mod bounded;
+#[cfg(feature = "alloc")]
+mod branded;
+#[cfg(feature = "std")]
+mod concurrent;
+#[cfg(feature = "alloc")]
+mod entry;
+#[cfg(feature = "alloc")]
+mod generational;
+#[cfg(feature = "alloc")]
+mod iter;
+mod key;
+#[cfg(feature = "alloc")]
+mod pinned;
+#[cfg(feature = "alloc")]
+mod raw;
+#[cfg(feature = "rayon")]
+mod rayon;
+#[cfg(feature = "alloc")]
+mod remap;
+#[cfg(feature = "alloc")]
+mod secondary;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "alloc")]
+mod token;
+
+#[cfg(feature = "alloc")]
+pub use append_only::AppendOnlyTypedSlab;
+pub use array::ArrayTypedSlab;
+#[cfg(feature = "alloc")]
+pub use bounded::BoundedTypedSlab;
+#[cfg(feature = "alloc")]
+pub use branded::{BrandedKey, BrandedTypedSlab};
+#[cfg(feature = "std")]
+pub use concurrent::{ConcurrentTypedSlab, ReadGuard, WriteGuard};
+#[cfg(feature = "alloc")]
+pub use entry::{TypedEntry, TypedOccupiedEntry, TypedVacantEntry, TypedVacantKeyEntry};
+#[cfg(feature = "alloc")]
+pub use generational::{GenKey, GenTypedSlab};
+#[cfg(feature = "alloc")]
+pub use iter::{Drain, ExtractIf, IntoIter, Iter, IterMut, Keys, Values, ValuesMut};
+pub use key::{IndexOverflow, SlabKey};
+#[cfg(feature = "alloc")]
+pub use pinned::PinnedTypedSlab;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub use raw::Reserved;
+#[cfg(feature = "alloc")]
+pub use raw::{
+    InsertAtError, RawDrain, RawIntoIter, RawIter, RawIterMut, RawSlab, RawVacantEntry, ReusePolicy,
+};
+#[cfg(feature = "alloc")]
+pub use remap::KeyRemap;
+#[cfg(feature = "alloc")]
+pub use secondary::{SecondaryMap, SparseSecondaryMap};
+#[cfg(feature = "alloc")]
+pub use token::{Scope, ValidKey};
+#[cfg(feature = "derive")]
+pub use typed_slab_derive::SlabKey;
+
+use core::fmt;
+#[cfg(feature = "alloc")]
+use core::hash::{Hash, Hasher};
+#[cfg(feature = "alloc")]
+use core::marker::PhantomData;
+#[cfg(feature = "alloc")]
+use core::ops::{Index, IndexMut};
+#[cfg(feature = "deref")]
 use derive_more::{Deref, DerefMut};
-use std::marker::PhantomData;
 
 /// Pre-allocated storage for a uniform data type with indeces
 /// converted from and into `usize`.
-#[derive(Debug, Deref, DerefMut)]
+///
+/// The untyped [`RawSlab`] is available with [`TypedSlab::as_raw`]
+/// and [`TypedSlab::as_raw_mut`], or with `Deref` to it if the `deref`
+/// feature is enabled.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+#[cfg_attr(feature = "deref", derive(Deref, DerefMut))]
 pub struct TypedSlab<K, V> {
-    #[deref]
-    #[deref_mut]
-    slab: Slab<V>,
+    #[cfg_attr(feature = "deref", deref)]
+    #[cfg_attr(feature = "deref", deref_mut)]
+    slab: RawSlab<V>,
     _key: PhantomData<K>,
 }
 
+#[cfg(feature = "alloc")]
 impl<K, V> Default for TypedSlab<K, V> {
     fn default() -> Self {
         Self {
-            slab: Slab::default(),
+            slab: RawSlab::default(),
             _key: PhantomData,
         }
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<K, V> TypedSlab<K, V>
 where
-    K: From<usize> + Into<usize>,
+    K: SlabKey,
 {
     /// Construct a new, empty `TypedSlab`.
     pub fn new() -> Self {
         Self {
-            slab: Slab::new(),
+            slab: RawSlab::new(),
+            _key: PhantomData,
+        }
+    }
+
+    /// Construct a new, empty `TypedSlab` with the specified capacity.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slab: RawSlab::with_capacity(capacity),
+            _key: PhantomData,
+        }
+    }
+
+    /// Construct a new, empty `TypedSlab` that reuses keys of removed values
+    /// by the policy.
+    pub fn with_policy(policy: ReusePolicy) -> Self {
+        Self {
+            slab: RawSlab::with_policy(policy),
             _key: PhantomData,
         }
     }
 
+    /// Construct a new, empty `TypedSlab` with the specified capacity
+    /// that reuses keys of removed values by the policy.
+    pub fn with_capacity_and_policy(capacity: usize, policy: ReusePolicy) -> Self {
+        Self {
+            slab: RawSlab::with_capacity_and_policy(capacity, policy),
+            _key: PhantomData,
+        }
+    }
+
+    /// Return a reference to the untyped slab.
+    pub fn as_raw(&self) -> &RawSlab<V> {
+        &self.slab
+    }
+
+    /// Return a mutable reference to the untyped slab.
+    pub fn as_raw_mut(&mut self) -> &mut RawSlab<V> {
+        &mut self.slab
+    }
+
+    /// Return the number of values the slab can store without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Reserve capacity for at least `additional` more values.
+    pub fn reserve(&mut self, additional: usize) {
+        self.slab.reserve(additional);
+    }
+
+    /// Reserve the minimum capacity for exactly `additional` more values.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.slab.reserve_exact(additional);
+    }
+
+    /// Shrink the capacity of the slab as much as possible
+    /// without invalidating keys.
+    pub fn shrink_to_fit(&mut self) {
+        self.slab.shrink_to_fit();
+    }
+
+    /// Remove all values from the slab.
+    pub fn clear(&mut self) {
+        self.slab.clear();
+    }
+
+    /// Return the key of the next vacant entry.
+    ///
+    /// The key is assigned by the next insertion: keys of dropped reservations
+    /// are returned to the free list after the key of an insertion is chosen.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index can't be represented by the key.
+    ///
+    pub fn vacant_key(&self) -> K {
+        K::from_index(self.slab.vacant_key())
+    }
+
     /// Insert a value in the slab, returning key assigned to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
     pub fn insert(&mut self, value: V) -> K {
-        let idx = self.slab.insert(value);
-        K::from(idx)
+        let entry = self.vacant_entry();
+        let key = entry.key();
+        entry.insert(value);
+        key
+    }
+
+    /// Insert a value in the slab at the given key, returning the value
+    /// that was associated with the key before.
+    ///
+    /// If the key is beyond the end of the slab, the storage grows and
+    /// the skipped keys become vacant, so they are assigned by later inserts
+    /// in the order described by [`RawSlab::insert_at`].
+    pub fn insert_at(&mut self, key: K, value: V) -> Result<Option<V>, InsertAtError> {
+        self.slab.insert_at(key.index(), value)
     }
 
     /// Insert a value in the slab, returning key assigned and a reference
     /// to the stored value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
     pub fn insert_entry(&mut self, value: V) -> (K, &mut V) {
         let entry = self.slab.vacant_entry();
-        let idx = entry.key();
+        let key = K::from_index(entry.key());
         let value_mut = entry.insert(value);
-        (K::from(idx), value_mut)
+        (key, value_mut)
+    }
+
+    /// Insert a value constructed from the key assigned to it,
+    /// returning the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn insert_with<F>(&mut self, f: F) -> K
+    where
+        F: FnOnce(K) -> V,
+    {
+        let entry = self.vacant_entry();
+        let value = f(entry.key());
+        let key = entry.key();
+        entry.insert(value);
+        key
+    }
+
+    /// Try to insert a value constructed from the key assigned to it,
+    /// returning the key. The slab is left unchanged if the constructor fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn try_insert_with<F, E>(&mut self, f: F) -> Result<K, E>
+    where
+        F: FnOnce(K) -> Result<V, E>,
+    {
+        let entry = self.vacant_entry();
+        let value = f(entry.key())?;
+        let key = entry.key();
+        entry.insert(value);
+        Ok(key)
+    }
+
+    /// Return a handle to a vacant entry that allows to learn the key
+    /// before inserting a value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index of the entry can't be represented by the key.
+    ///
+    pub fn vacant_entry(&mut self) -> TypedVacantEntry<'_, K, V> {
+        let entry = TypedVacantEntry::new(self.slab.vacant_entry());
+        entry.key();
+        entry
+    }
+
+    /// Return the entry associated with the given key.
+    pub fn entry(&mut self, key: K) -> TypedEntry<'_, K, V> {
+        TypedEntry::new(&mut self.slab, key)
+    }
+
+    /// Take a vacant entry out of the free list, so its key can be handed out
+    /// before the value is constructed. The key isn't associated with a value
+    /// until the reservation is filled with [`TypedSlab::fill`].
+    ///
+    /// If the reservation is cancelled or dropped, the key is returned
+    /// to the free list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the reserved index can't be represented by the key.
+    ///
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn reserve_key(&mut self) -> Reserved<K> {
+        let reserved = self.slab.reserve_key().retype();
+        reserved.key();
+        reserved
+    }
+
+    /// Store the value in the reserved entry, returning the key of it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the reservation was made by another slab
+    /// or the slab was cleared or drained after it.
+    ///
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn fill(&mut self, reserved: Reserved<K>, value: V) -> K {
+        K::from_index(self.slab.fill(reserved, value))
+    }
+
+    /// Return the reserved key to the free list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the reservation was made by another slab
+    /// or the slab was cleared or drained after it.
+    ///
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn cancel(&mut self, reserved: Reserved<K>) {
+        self.slab.cancel(reserved);
     }
 
     /// Remove and return the value associated with the given key.
     /// The key is then released and may be associated with future stored values.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let idx = key.index();
+        self.slab.try_remove(idx)
+    }
+
+    /// Return true if a value is associated with the given key.
+    pub fn contains(&self, key: K) -> bool {
+        let idx = key.index();
+        self.slab.contains(idx)
+    }
+
+    /// Return the key of the value that is stored in the slab.
     ///
     /// # Panics
     ///
-    /// Panics if key is not associated with a value.
+    /// Panics if the value is not stored in the slab.
     ///
-    pub fn remove(&mut self, key: K) -> Option<V> {
-        let idx = key.into();
-        if self.slab.contains(idx) {
-            let value = self.slab.remove(idx);
-            Some(value)
-        } else {
-            None
-        }
+    pub fn key_of(&self, value: &V) -> K {
+        K::from_index(self.slab.key_of(value))
     }
 
     /// Return a reference to the value associated with the given key.
     /// If the given key is not associated with a value, then `None` is returned.
     pub fn get(&self, key: K) -> Option<&V> {
-        let idx = key.into();
+        let idx = key.index();
         self.slab.get(idx)
     }
 
     /// Return a mutable reference to the value associated with the given key.
     /// If the given key is not associated with a value, then `None` is returned.
     pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
-        let idx = key.into();
+        let idx = key.index();
         self.slab.get_mut(idx)
     }
 
+    /// Return mutable references to the values associated with two keys.
+    /// If any of the keys is not associated with a value or both keys
+    /// are the same, then `None` is returned.
+    pub fn get2_mut(&mut self, key1: K, key2: K) -> Option<(&mut V, &mut V)> {
+        let idx1 = key1.index();
+        let idx2 = key2.index();
+        if idx1 == idx2 {
+            None
+        } else {
+            self.slab.get2_mut(idx1, idx2)
+        }
+    }
+
+    /// Return mutable references to the values associated with many keys.
+    pub fn get_disjoint_mut<const N: usize>(
+        &mut self,
+        keys: [K; N],
+    ) -> Result<[&mut V; N], GetDisjointMutError> {
+        let idxs = keys.map(|key| key.index());
+        self.slab.get_disjoint_mut(idxs)
+    }
+
     /// Return true if there are no values stored in the slab.
     pub fn is_empty(&self) -> bool {
         self.slab.is_empty()
     }
 
     /// Return an iterator over the slab.
-    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (K, &V)> {
-        self.slab.iter().map(|(idx, v)| (idx.into(), v))
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter::new(self.slab.iter())
     }
 
     /// Return an iterator that allows modifying each value.
-    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = (K, &mut V)> {
-        self.slab.iter_mut().map(|(idx, v)| (idx.into(), v))
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut::new(self.slab.iter_mut())
+    }
+
+    /// Return an iterator over the keys of the slab.
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys::new(self.slab.iter())
     }
 
     /// Return an iterator with references to values.
-    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> {
-        self.slab.iter().map(|(_, v)| v)
+    pub fn values(&self) -> Values<'_, V> {
+        Values::new(self.slab.iter())
+    }
+
+    /// Return an iterator with mutable references to values.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, V> {
+        ValuesMut::new(self.slab.iter_mut())
     }
 
     /// Return a draining iterator that removes all elements from the slab
     /// and yields the removed items.
-    pub fn drain(&mut self) -> impl DoubleEndedIterator<Item = V> + '_ {
-        self.slab.drain()
+    pub fn drain(&mut self) -> Drain<'_, V> {
+        Drain::new(self.slab.drain())
     }
 
     /// Return a number of stored values.
     pub fn len(&self) -> usize {
         self.slab.len()
     }
+
+    /// Move values to vacant entries at the beginning of the slab
+    /// and release the memory at the end of it.
+    /// The closure is called with the old and the new key of every moved value.
+    /// Reserved entries are never moved, so the compaction stops
+    /// at the last of them.
+    pub fn compact<F>(&mut self, mut rekey: F)
+    where
+        F: FnMut(K, K),
+    {
+        self.slab.compact(|_, from, to| {
+            rekey(K::from_index(from), K::from_index(to));
+            true
+        });
+    }
+
+    /// Move values to vacant entries at the beginning of the slab
+    /// and release the memory at the end of it, returning the table
+    /// of moved keys. Like [`TypedSlab::compact`], the compaction stops
+    /// at the last reserved entry.
+    pub fn compact_with_remap(&mut self) -> KeyRemap<K> {
+        let mut remap = KeyRemap::default();
+        self.slab.compact(|_, from, to| {
+            remap.insert(from, to);
+            true
+        });
+        remap
+    }
+
+    /// Retain only the values for which the predicate returns true.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        self.slab.retain(|idx, v| f(K::from_index(idx), v));
+    }
+
+    /// Return an iterator that removes the values for which the predicate
+    /// returns true and yields them with their keys.
+    pub fn extract_if<F>(&mut self, f: F) -> ExtractIf<'_, K, V, F>
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        ExtractIf::new(&mut self.slab, f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K, V> Clone for TypedSlab<K, V>
+where
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            slab: self.slab.clone(),
+            _key: PhantomData,
+        }
+    }
+}
+
+/// Slabs are equal if they associate equal values with the same keys.
+#[cfg(feature = "alloc")]
+impl<K, V> PartialEq for TypedSlab<K, V>
+where
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.slab.len() == other.slab.len() && self.slab.iter().eq(other.slab.iter())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K, V> Eq for TypedSlab<K, V> where V: Eq {}
+
+#[cfg(feature = "alloc")]
+impl<K, V> Hash for TypedSlab<K, V>
+where
+    V: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.slab.len());
+        for pair in self.slab.iter() {
+            pair.hash(state);
+        }
+    }
 }
 
-#[cfg(test)]
+#[cfg(feature = "alloc")]
+impl<K, V> Index<K> for TypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    type Output = V;
+
+    /// # Panics
+    ///
+    /// Panics if the key is not associated with a value.
+    ///
+    fn index(&self, key: K) -> &V {
+        self.get(key).expect("invalid key")
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K, V> IndexMut<K> for TypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    /// # Panics
+    ///
+    /// Panics if the key is not associated with a value.
+    ///
+    fn index_mut(&mut self, key: K) -> &mut V {
+        self.get_mut(key).expect("invalid key")
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K, V> IntoIterator for TypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter::new(self.slab.into_iter())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, K, V> IntoIterator for &'a TypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    type Item = (K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, K, V> IntoIterator for &'a mut TypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    type Item = (K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K, V> FromIterator<V> for TypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        let mut slab = Self::new();
+        slab.extend(iter);
+        slab
+    }
+}
+
+/// Values are stored with the given keys. If a key is repeated,
+/// then the last value is stored.
+///
+/// # Panics
+///
+/// Panics if the slab can't grow to contain a key.
+///
+#[cfg(feature = "alloc")]
+impl<K, V> FromIterator<(K, V)> for TypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut slab = Self::new();
+        for (key, value) in iter {
+            if let Err(err) = slab.insert_at(key, value) {
+                panic!("{}", err);
+            }
+        }
+        slab
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K, V> Extend<V> for TypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+/// The error returned by [`TypedSlab::get_disjoint_mut`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GetDisjointMutError {
+    /// A key is not associated with a value.
+    MissingKey,
+    /// A key is passed more than once.
+    DuplicateKey,
+}
+
+impl fmt::Display for GetDisjointMutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingKey => write!(f, "a key is not associated with a value"),
+            Self::DuplicateKey => write!(f, "a key is passed more than once"),
+        }
+    }
+}
+
+impl core::error::Error for GetDisjointMutError {}
+
+#[cfg(all(test, feature = "alloc"))]
 mod test {
     use super::*;
 
@@ -125,4 +660,237 @@ mod test {
         let slab: TypedSlab<usize, ()> = TypedSlab::new();
         let _iter = slab.iter().rev();
     }
+
+    #[test]
+    fn test_derive_key() {
+        #[derive(typed_slab_derive::SlabKey, Clone, Copy)]
+        struct Id(u16);
+
+        let mut slab: TypedSlab<Id, &str> = TypedSlab::new();
+        let key = slab.insert("value");
+        assert_eq!(key, Id(0));
+        assert_eq!(key.to_string(), "0");
+        assert_eq!(slab.get(key), Some(&"value"));
+        assert_eq!(slab.key_of(slab.as_raw().get(0).unwrap()), key);
+        let overflow = usize::from(u16::MAX) + 1;
+        assert_eq!(
+            Id::try_from_index(overflow),
+            Err(IndexOverflow { index: overflow })
+        );
+
+        #[derive(typed_slab_derive::SlabKey, Clone, Copy)]
+        struct Sid(i32);
+
+        let mut slab: TypedSlab<Sid, &str> = TypedSlab::new();
+        slab.insert("value");
+        assert_eq!(Sid(-1).index(), usize::MAX);
+        assert_eq!(slab.get(Sid(-1)), None);
+        assert!(!slab.contains(Sid(-1)));
+        assert_eq!(slab.remove(Sid(-1)), None);
+        assert_eq!(
+            slab.insert_at(Sid(-1), "other"),
+            Err(InsertAtError::CapacityOverflow)
+        );
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut slab: TypedSlab<usize, &str> = TypedSlab::new();
+        let entry = slab.vacant_entry();
+        let key = entry.key();
+        entry.insert("value");
+        assert_eq!(slab.get(key), Some(&"value"));
+        match slab.entry(key) {
+            TypedEntry::Occupied(mut entry) => assert_eq!(entry.insert("other"), "value"),
+            TypedEntry::Vacant(_) => panic!("entry must be occupied"),
+        }
+        match slab.entry(3) {
+            TypedEntry::Occupied(_) => panic!("entry must be vacant"),
+            TypedEntry::Vacant(entry) => assert_eq!(entry.key(), 3),
+        }
+        assert_eq!(slab.entry(key).or_insert("default"), &"other");
+        *slab.entry(3).or_insert_with(|| "default") = "inserted";
+        assert_eq!(slab.get(3), Some(&"inserted"));
+        assert_eq!(slab.insert("gap"), 1);
+    }
+
+    #[test]
+    fn test_insert_at() {
+        let mut slab: TypedSlab<usize, &str> = TypedSlab::new();
+        assert_eq!(slab.insert_at(3, "d"), Ok(None));
+        assert_eq!(slab.insert_at(1, "b"), Ok(None));
+        assert_eq!(slab.insert_at(3, "x"), Ok(Some("d")));
+        assert_eq!(slab.insert("a"), 0);
+        assert_eq!(slab.insert("c"), 2);
+        assert_eq!(slab.insert("e"), 4);
+        let restored: TypedSlab<usize, &str> = slab.iter().rev().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(restored, slab);
+    }
+
+    #[test]
+    #[cfg(target_has_atomic = "ptr")]
+    fn test_reserve_key() {
+        let mut slab: TypedSlab<usize, &str> = TypedSlab::new();
+        let reserved = slab.reserve_key();
+        let key = reserved.key();
+        assert_eq!(slab.get(key), None);
+        assert_eq!(slab.insert("other"), 1);
+        assert_eq!(slab.insert_at(key, "x"), Err(InsertAtError::Reserved));
+        assert_eq!(slab.fill(reserved, "value"), key);
+        assert_eq!(slab.get(key), Some(&"value"));
+        let dropped = slab.reserve_key().key();
+        assert_eq!(slab.insert("again"), 3);
+        assert_eq!(slab.vacant_key(), dropped);
+        assert_eq!(slab.insert("dropped"), dropped);
+        let cancelled = slab.reserve_key();
+        let key = cancelled.key();
+        slab.cancel(cancelled);
+        assert_eq!(slab.insert("last"), key);
+    }
+
+    #[test]
+    fn test_reuse_policy() {
+        let reused = |policy| {
+            let mut slab: TypedSlab<usize, usize> = TypedSlab::with_policy(policy);
+            slab.extend(0..4);
+            slab.remove(2);
+            slab.remove(0);
+            slab.remove(1);
+            [slab.insert(4), slab.insert(5)]
+        };
+        assert_eq!(reused(ReusePolicy::Lifo), [1, 0]);
+        assert_eq!(reused(ReusePolicy::Fifo), [2, 0]);
+        assert_eq!(reused(ReusePolicy::LowestFirst), [0, 1]);
+        assert_eq!(reused(ReusePolicy::Quarantine(1)), [4, 2]);
+
+        let mut slab: TypedSlab<usize, usize> =
+            TypedSlab::with_capacity_and_policy(4, ReusePolicy::Quarantine(2));
+        slab.extend(0..4);
+        slab.remove(1);
+        slab.remove(2);
+        assert_eq!(slab.insert_at(2, 2), Ok(None));
+        assert_eq!([slab.insert(4), slab.insert(5)], [4, 1]);
+        assert_eq!(slab.as_raw().policy(), ReusePolicy::Quarantine(2));
+
+        let mut slab: TypedSlab<usize, usize> = TypedSlab::with_policy(ReusePolicy::Fifo);
+        slab.extend(0..2);
+        slab.remove(0);
+        assert_eq!(slab.insert_at(5, 5), Ok(None));
+        assert_eq!(
+            [
+                slab.insert(6),
+                slab.insert(7),
+                slab.insert(8),
+                slab.insert(9)
+            ],
+            [0, 2, 3, 4]
+        );
+
+        let mut slab: TypedSlab<usize, usize> =
+            TypedSlab::with_policy(ReusePolicy::Quarantine(100));
+        slab.extend(0..64);
+        for key in 0..64 {
+            slab.remove(key);
+        }
+        slab.reserve(64);
+        let capacity = slab.capacity();
+        slab.extend(0..64);
+        assert_eq!(slab.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_insert_with() {
+        let mut slab: TypedSlab<usize, usize> = TypedSlab::new();
+        let key = slab.insert_with(|key| key + 10);
+        assert_eq!(slab.get(key), Some(&10));
+        let result: Result<usize, ()> = slab.try_insert_with(|_| Err(()));
+        assert_eq!(result, Err(()));
+        assert_eq!(slab.len(), 1);
+        assert_eq!(slab.try_insert_with::<_, ()>(Ok), Ok(1));
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut slab: TypedSlab<usize, usize> = TypedSlab::new();
+        for value in 0..6 {
+            slab.insert(value * 10);
+        }
+        slab.retain(|key, _| key != 0);
+        let extracted: Vec<_> = slab.extract_if(|_, v| *v % 20 == 0).collect();
+        assert_eq!(extracted, [(2, 20), (4, 40)]);
+        assert_eq!(slab.values().copied().collect::<Vec<_>>(), [10, 30, 50]);
+    }
+
+    #[test]
+    fn test_disjoint_mut() {
+        let mut slab: TypedSlab<usize, usize> = TypedSlab::new();
+        let a = slab.insert(1);
+        let b = slab.insert(2);
+        let (x, y) = slab.get2_mut(a, b).unwrap();
+        std::mem::swap(x, y);
+        assert_eq!(slab.get(a), Some(&2));
+        assert!(slab.get2_mut(a, a).is_none());
+        assert_eq!(
+            slab.get_disjoint_mut([a, b, 5]).err(),
+            Some(GetDisjointMutError::MissingKey)
+        );
+        assert_eq!(
+            slab.get_disjoint_mut([b, a, b]).err(),
+            Some(GetDisjointMutError::DuplicateKey)
+        );
+        let [x, y] = slab.get_disjoint_mut([b, a]).unwrap();
+        *x += *y;
+        assert_eq!(slab.get(b), Some(&3));
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut slab: TypedSlab<usize, usize> = TypedSlab::new();
+        let mut extra = SecondaryMap::new();
+        for value in 0..4 {
+            let key = slab.insert(value);
+            extra.insert(key, value);
+        }
+        slab.remove(0);
+        slab.remove(1);
+        extra.purge(&slab);
+        let remap = slab.compact_with_remap();
+        extra.remap_keys(&remap);
+        assert_eq!(remap.len(), 2);
+        for (key, value) in slab.iter() {
+            assert_eq!(extra.get(key), Some(value));
+        }
+        assert_eq!(slab.get(remap.remap(3)), Some(&3));
+    }
+
+    #[test]
+    fn test_std_traits() {
+        let mut slab: TypedSlab<usize, &str> = ["a", "b", "c"].into_iter().collect();
+        slab[1] = "x";
+        slab.extend(["d"]);
+        assert_eq!(slab[3], "d");
+        let pairs: Vec<_> = (&slab).into_iter().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(pairs, [(0, "a"), (1, "x"), (2, "c"), (3, "d")]);
+        let restored: TypedSlab<usize, &str> = [(5, "e"), (2, "c")].into_iter().collect();
+        assert_eq!(restored.get(5), Some(&"e"));
+        assert!(!restored.contains(0));
+        assert_eq!(slab.clone(), slab);
+        assert_ne!(restored, slab);
+        assert_eq!(slab.into_iter().next_back(), Some((3, "d")));
+    }
+
+    #[test]
+    fn test_exact_size() {
+        let mut slab: TypedSlab<usize, usize> = (0..5).collect();
+        slab.remove(2);
+        assert_eq!(slab.iter().len(), 4);
+        assert_eq!(slab.keys().rev().collect::<Vec<_>>(), [4, 3, 1, 0]);
+        for value in slab.values_mut() {
+            *value *= 2;
+        }
+        let mut values = slab.values();
+        values.next();
+        assert_eq!(values.len(), 3);
+        assert_eq!(slab.drain().len(), 4);
+    }
 }
@@ -0,0 +1,223 @@
+//! A slab that never moves stored values.
+
+use crate::SlabKey;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::pin::Pin;
+
+const CHUNK_SIZE: usize = 64;
+
+#[derive(Debug)]
+enum Slot<V> {
+    Vacant(usize),
+    Occupied(V),
+}
+
+/// Storage for a uniform data type that guarantees stable addresses
+/// of the stored values.
+///
+/// Values are stored in chunks that are never reallocated, so a value
+/// stays at the same address until it's removed, and can be pinned.
+/// Values that are not `Unpin` are dropped in place by [`PinnedTypedSlab::remove`].
+#[derive(Debug)]
+pub struct PinnedTypedSlab<K, V> {
+    chunks: Vec<Vec<Slot<V>>>,
+    entries: usize,
+    next: usize,
+    len: usize,
+    _key: PhantomData<K>,
+}
+
+impl<K, V> Default for PinnedTypedSlab<K, V> {
+    fn default() -> Self {
+        Self {
+            chunks: Vec::new(),
+            entries: 0,
+            next: 0,
+            len: 0,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K, V> PinnedTypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    /// Construct a new, empty `PinnedTypedSlab`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn slot(&self, idx: usize) -> Option<&Slot<V>> {
+        self.chunks.get(idx / CHUNK_SIZE)?.get(idx % CHUNK_SIZE)
+    }
+
+    fn slot_mut(&mut self, idx: usize) -> Option<&mut Slot<V>> {
+        self.chunks
+            .get_mut(idx / CHUNK_SIZE)?
+            .get_mut(idx % CHUNK_SIZE)
+    }
+
+    fn value_mut(&mut self, idx: usize) -> Option<&mut V> {
+        match self.slot_mut(idx)? {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant(_) => None,
+        }
+    }
+
+    /// Insert a value in the slab, returning key assigned and
+    /// the pinned value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn insert(&mut self, value: V) -> (K, Pin<&mut V>) {
+        let idx = self.next;
+        let key = K::from_index(idx);
+        if idx == self.entries {
+            match self.chunks.last_mut() {
+                Some(chunk) if chunk.len() < CHUNK_SIZE => chunk.push(Slot::Occupied(value)),
+                _ => {
+                    // The chunk never grows over the capacity, so it's never reallocated.
+                    let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+                    chunk.push(Slot::Occupied(value));
+                    self.chunks.push(chunk);
+                }
+            }
+            self.entries += 1;
+            self.next = self.entries;
+        } else {
+            let slot = self.slot_mut(idx).expect("vacant slot");
+            if let Slot::Vacant(next) = core::mem::replace(slot, Slot::Occupied(value)) {
+                self.next = next;
+            }
+        }
+        self.len += 1;
+        let value = self.value_mut(idx).expect("inserted value");
+        // SAFETY: the value is never moved, because chunks are never reallocated
+        // and the value is only dropped in place or moved out if it's `Unpin`.
+        let value = unsafe { Pin::new_unchecked(value) };
+        (key, value)
+    }
+
+    /// Drop the value associated with the given key in place.
+    /// Return false if the given key is not associated with a value.
+    pub fn remove(&mut self, key: K) -> bool {
+        let idx = key.index();
+        let next = self.next;
+        match self.slot_mut(idx) {
+            Some(slot @ Slot::Occupied(_)) => {
+                *slot = Slot::Vacant(next);
+                self.next = idx;
+                self.len -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Remove and return the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn take(&mut self, key: K) -> Option<V>
+    where
+        V: Unpin,
+    {
+        let idx = key.index();
+        let next = self.next;
+        let slot = self.slot_mut(idx)?;
+        if let Slot::Vacant(_) = slot {
+            return None;
+        }
+        let value = core::mem::replace(slot, Slot::Vacant(next));
+        self.next = idx;
+        self.len -= 1;
+        match value {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant(_) => None,
+        }
+    }
+
+    /// Return a reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get(&self, key: K) -> Option<&V> {
+        match self.slot(key.index())? {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant(_) => None,
+        }
+    }
+
+    /// Return the pinned value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get_pin_mut(&mut self, key: K) -> Option<Pin<&mut V>> {
+        let value = self.value_mut(key.index())?;
+        // SAFETY: see `insert`.
+        Some(unsafe { Pin::new_unchecked(value) })
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V>
+    where
+        V: Unpin,
+    {
+        self.value_mut(key.index())
+    }
+
+    /// Return true if a value is associated with the given key.
+    pub fn contains(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Return true if there are no values stored in the slab.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return a number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Drop all values in place and release the memory.
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.entries = 0;
+        self.next = 0;
+        self.len = 0;
+    }
+
+    /// Return an iterator over the slab.
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.chunks
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(idx, slot)| match slot {
+                Slot::Occupied(value) => Some((K::from_index(idx), value)),
+                Slot::Vacant(_) => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stable_address() {
+        let mut slab: PinnedTypedSlab<usize, usize> = PinnedTypedSlab::new();
+        let (first, value) = slab.insert(0);
+        let addr: *const usize = &*value;
+        for value in 1..CHUNK_SIZE * 4 {
+            slab.insert(value);
+        }
+        assert!(std::ptr::eq(addr, slab.get(first).unwrap()));
+        assert!(slab.remove(first));
+        assert_eq!(slab.take(1), Some(1));
+        assert_eq!(slab.insert(10).0, 1);
+        assert_eq!(slab.insert(20).0, first);
+        assert_eq!(slab.len(), CHUNK_SIZE * 4);
+    }
+}
@@ -0,0 +1,1416 @@
+//! Untyped storage of slabs with a free list that links vacant entries
+//! in both directions and reuses them by a configurable policy.
+
+use crate::GetDisjointMutError;
+#[cfg(target_has_atomic = "ptr")]
+use crate::SlabKey;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeSet, VecDeque};
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use alloc::vec::{self, Vec};
+#[cfg(all(target_has_atomic = "ptr", not(feature = "std")))]
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::iter::{Enumerate, FusedIterator};
+#[cfg(target_has_atomic = "ptr")]
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Index, IndexMut};
+#[cfg(target_has_atomic = "ptr")]
+use core::panic::RefUnwindSafe;
+use core::slice;
+#[cfg(all(target_has_atomic = "ptr", not(feature = "std")))]
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "rayon")]
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator,
+    ParallelDrainRange, ParallelIterator,
+};
+
+/// The end of the free list.
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+enum Slot<V> {
+    /// Unlinked vacant entries are kept by the policy outside of the list.
+    Vacant {
+        prev: usize,
+        next: usize,
+    },
+    Reserved,
+    Occupied(V),
+}
+
+impl<V> Slot<V> {
+    const UNLINKED: Self = Self::Vacant {
+        prev: NIL,
+        next: NIL,
+    };
+}
+
+/// The order in which keys of removed values are assigned to new values.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReusePolicy {
+    /// The most recently released key is reused first.
+    #[default]
+    Lifo,
+    /// The least recently released key is reused first.
+    Fifo,
+    /// The lowest released key is reused first.
+    LowestFirst,
+    /// Released keys are reused in the order of release, but only after
+    /// the given number of insertions.
+    Quarantine(usize),
+}
+
+/// The state of the policy besides the head of the free list.
+#[derive(Debug, Clone)]
+enum Reuse {
+    Lifo,
+    Fifo {
+        tail: usize,
+    },
+    /// Boxed, so other policies don't pay for the size of the set.
+    #[allow(clippy::box_collection)]
+    LowestFirst(Box<BTreeSet<usize>>),
+    Quarantine(Box<Quarantine>),
+}
+
+impl From<ReusePolicy> for Reuse {
+    fn from(policy: ReusePolicy) -> Self {
+        match policy {
+            ReusePolicy::Lifo => Self::Lifo,
+            ReusePolicy::Fifo => Self::Fifo { tail: NIL },
+            ReusePolicy::LowestFirst => Self::LowestFirst(Box::default()),
+            ReusePolicy::Quarantine(delay) => Self::Quarantine(Box::new(Quarantine {
+                delay,
+                inserts: 0,
+                held: VecDeque::new(),
+            })),
+        }
+    }
+}
+
+/// Released keys with the number of insertions after which they can be reused.
+#[derive(Debug, Clone)]
+struct Quarantine {
+    delay: usize,
+    inserts: usize,
+    held: VecDeque<(usize, usize)>,
+}
+
+/// Pre-allocated storage for a uniform data type with `usize` keys.
+///
+/// Unlike [`slab::Slab`](https://docs.rs/slab), vacant entries are linked
+/// in both directions, so a value can be placed at any vacant key without
+/// walking the free list, and the order of key reuse is set by a [`ReusePolicy`].
+/// In exchange, a vacant entry holds two words instead of one, so entries
+/// of values smaller than two words take a word more than in `Slab`.
+pub struct RawSlab<V> {
+    entries: Vec<Slot<V>>,
+    len: usize,
+    reserved: usize,
+    head: usize,
+    reuse: Reuse,
+    /// Created by the first reservation and replaced when the slab is emptied,
+    /// so reservations made before can't be filled.
+    #[cfg(target_has_atomic = "ptr")]
+    dropped: Option<Arc<DropQueue>>,
+}
+
+impl<V> Clone for RawSlab<V>
+where
+    V: Clone,
+{
+    /// Reserved entries are vacant in the clone,
+    /// since the reservations belong to the original slab.
+    fn clone(&self) -> Self {
+        let mut slab = Self {
+            entries: self.entries.clone(),
+            len: self.len,
+            reserved: 0,
+            head: self.head,
+            reuse: self.reuse.clone(),
+            #[cfg(target_has_atomic = "ptr")]
+            dropped: None,
+        };
+        if self.reserved > 0 {
+            for key in 0..slab.entries.len() {
+                if let Slot::Reserved = slab.entries[key] {
+                    slab.link(key);
+                }
+            }
+        }
+        slab
+    }
+}
+
+impl<V> Default for RawSlab<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> RawSlab<V> {
+    /// Construct a new, empty `RawSlab`.
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            len: 0,
+            reserved: 0,
+            head: NIL,
+            reuse: Reuse::Lifo,
+            #[cfg(target_has_atomic = "ptr")]
+            dropped: None,
+        }
+    }
+
+    /// Construct a new, empty `RawSlab` with the specified capacity.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_policy(capacity, ReusePolicy::Lifo)
+    }
+
+    /// Construct a new, empty `RawSlab` that reuses keys by the policy.
+    pub fn with_policy(policy: ReusePolicy) -> Self {
+        Self::with_capacity_and_policy(0, policy)
+    }
+
+    /// Construct a new, empty `RawSlab` with the specified capacity
+    /// that reuses keys by the policy.
+    pub fn with_capacity_and_policy(capacity: usize, policy: ReusePolicy) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            reuse: policy.into(),
+            ..Self::new()
+        }
+    }
+
+    /// Return the policy of key reuse.
+    pub fn policy(&self) -> ReusePolicy {
+        match &self.reuse {
+            Reuse::Lifo => ReusePolicy::Lifo,
+            Reuse::Fifo { .. } => ReusePolicy::Fifo,
+            Reuse::LowestFirst(_) => ReusePolicy::LowestFirst,
+            Reuse::Quarantine(quarantine) => ReusePolicy::Quarantine(quarantine.delay),
+        }
+    }
+
+    /// Return the number of values the slab can store without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    /// Reserve capacity for at least `additional` more values.
+    pub fn reserve(&mut self, additional: usize) {
+        let reusable = self.reusable();
+        self.entries.reserve(additional.saturating_sub(reusable));
+    }
+
+    /// Reserve the minimum capacity for exactly `additional` more values.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        let reusable = self.reusable();
+        self.entries
+            .reserve_exact(additional.saturating_sub(reusable));
+    }
+
+    /// Shrink the capacity of the slab as much as possible
+    /// without invalidating keys.
+    pub fn shrink_to_fit(&mut self) {
+        self.release_dropped();
+        self.release_vacant_tail();
+        self.entries.shrink_to_fit();
+    }
+
+    /// Remove all values from the slab.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.len = 0;
+        self.reset_free_list();
+    }
+
+    /// Return the key of the next vacant entry.
+    ///
+    /// The key is assigned by the next insertion: keys of dropped reservations
+    /// are returned to the free list after the key of an insertion is chosen.
+    pub fn vacant_key(&self) -> usize {
+        let held = match &self.reuse {
+            Reuse::LowestFirst(lowest) => lowest.first().copied(),
+            Reuse::Quarantine(quarantine) => match quarantine.held.front() {
+                Some(&(key, until)) if until <= quarantine.inserts => Some(key),
+                _ => None,
+            },
+            Reuse::Lifo | Reuse::Fifo { .. } => None,
+        };
+        match self.head {
+            NIL => held.unwrap_or(self.entries.len()),
+            head => head,
+        }
+    }
+
+    /// Insert a value in the slab, returning key assigned to the value.
+    pub fn insert(&mut self, value: V) -> usize {
+        let key = self.vacant_key();
+        self.occupy(key, value);
+        self.release_dropped();
+        key
+    }
+
+    /// Insert a value in the slab at the given key, returning the value
+    /// that was associated with the key before.
+    ///
+    /// If the key is beyond the end of the slab, the storage grows and
+    /// the skipped entries become vacant. The skipped keys are reused
+    /// in ascending order: before the released keys by the `Lifo` and
+    /// `Quarantine` policies, and after them by the `Fifo` policy.
+    pub fn insert_at(&mut self, key: usize, value: V) -> Result<Option<V>, InsertAtError> {
+        self.release_dropped();
+        match self.entries.get_mut(key) {
+            Some(Slot::Occupied(old)) => return Ok(Some(mem::replace(old, value))),
+            Some(Slot::Reserved) => return Err(InsertAtError::Reserved),
+            Some(Slot::Vacant { .. }) => {}
+            None => self.grow(key)?,
+        }
+        self.occupy(key, value);
+        Ok(None)
+    }
+
+    /// Return a handle to a vacant entry that allows to learn the key
+    /// before inserting a value.
+    pub fn vacant_entry(&mut self) -> RawVacantEntry<'_, V> {
+        let key = self.vacant_key();
+        self.release_dropped();
+        RawVacantEntry { key, slab: self }
+    }
+
+    /// Take a vacant entry out of the free list, so its key can be handed out
+    /// before the value is constructed.
+    ///
+    /// The entry stays vacant until the reservation is filled with
+    /// [`RawSlab::fill`]. If the reservation is cancelled or dropped,
+    /// the key is returned to the free list.
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn reserve_key(&mut self) -> Reserved<usize> {
+        let key = self.vacant_key();
+        self.place(key, Slot::Reserved);
+        self.reserved += 1;
+        self.release_dropped();
+        let queue = self.dropped.get_or_insert_with(Arc::default);
+        Reserved {
+            idx: key,
+            queue: Some(Arc::clone(queue)),
+            _key: PhantomData,
+        }
+    }
+
+    /// Store the value in the reserved entry, returning the key of it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the reservation was made by another slab
+    /// or the slab was cleared or drained after it.
+    ///
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn fill<K>(&mut self, reserved: Reserved<K>, value: V) -> usize {
+        let key = self.take_reservation(reserved);
+        self.entries[key] = Slot::Occupied(value);
+        self.len += 1;
+        key
+    }
+
+    /// Return the reserved key to the free list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the reservation was made by another slab
+    /// or the slab was cleared or drained after it.
+    ///
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn cancel<K>(&mut self, reserved: Reserved<K>) {
+        let key = self.take_reservation(reserved);
+        self.link(key);
+    }
+
+    /// Remove and return the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn try_remove(&mut self, key: usize) -> Option<V> {
+        if !self.contains(key) {
+            return None;
+        }
+        let slot = mem::replace(&mut self.entries[key], Slot::UNLINKED);
+        self.link(key);
+        self.len -= 1;
+        match slot {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant { .. } | Slot::Reserved => None,
+        }
+    }
+
+    /// Remove and return the value associated with the given key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not associated with a value.
+    ///
+    pub fn remove(&mut self, key: usize) -> V {
+        self.try_remove(key).expect("invalid key")
+    }
+
+    /// Remove and return the value associated with the given key, keeping
+    /// the key out of use like a reservation that is never released.
+    /// The key is vacant in clones of the slab and after it's emptied.
+    pub(crate) fn retire(&mut self, key: usize) -> Option<V> {
+        if !self.contains(key) {
+            return None;
+        }
+        let slot = mem::replace(&mut self.entries[key], Slot::Reserved);
+        self.len -= 1;
+        self.reserved += 1;
+        take(slot)
+    }
+
+    /// Return true if a value is associated with the given key.
+    pub fn contains(&self, key: usize) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Return the key of the value that is stored in the slab.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not stored in the slab.
+    ///
+    pub fn key_of(&self, value: &V) -> usize {
+        let base = self.entries.as_ptr() as usize;
+        let offset = (value as *const V as usize)
+            .checked_sub(base)
+            .expect("the value is not stored in the slab");
+        let key = offset / mem::size_of::<Slot<V>>();
+        assert!(self.contains(key), "the value is not stored in the slab");
+        key
+    }
+
+    /// Return a reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get(&self, key: usize) -> Option<&V> {
+        match self.entries.get(key)? {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant { .. } | Slot::Reserved => None,
+        }
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut V> {
+        match self.entries.get_mut(key)? {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant { .. } | Slot::Reserved => None,
+        }
+    }
+
+    /// Return a reference to the value associated with the given key
+    /// without checking that the key is associated with a value.
+    ///
+    /// # Safety
+    ///
+    /// The key must be associated with a value.
+    ///
+    pub unsafe fn get_unchecked(&self, key: usize) -> &V {
+        // SAFETY: the caller guarantees that the key is associated with a value.
+        unsafe {
+            match self.entries.get_unchecked(key) {
+                Slot::Occupied(value) => value,
+                Slot::Vacant { .. } | Slot::Reserved => core::hint::unreachable_unchecked(),
+            }
+        }
+    }
+
+    /// Return mutable references to the values associated with two keys.
+    /// If any of the keys is not associated with a value or both keys
+    /// are the same, then `None` is returned.
+    pub fn get2_mut(&mut self, key1: usize, key2: usize) -> Option<(&mut V, &mut V)> {
+        let [value1, value2] = self.get_disjoint_mut([key1, key2]).ok()?;
+        Some((value1, value2))
+    }
+
+    /// Return mutable references to the values associated with many keys.
+    pub fn get_disjoint_mut<const N: usize>(
+        &mut self,
+        keys: [usize; N],
+    ) -> Result<[&mut V; N], GetDisjointMutError> {
+        for (i, key) in keys.iter().enumerate() {
+            if keys[..i].contains(key) {
+                return Err(GetDisjointMutError::DuplicateKey);
+            }
+            if !self.contains(*key) {
+                return Err(GetDisjointMutError::MissingKey);
+            }
+        }
+        let entries = self.entries.as_mut_ptr();
+        Ok(keys.map(|key| {
+            // SAFETY: the keys are distinct and associated with values,
+            // so the references don't alias.
+            match unsafe { &mut *entries.add(key) } {
+                Slot::Occupied(value) => value,
+                Slot::Vacant { .. } | Slot::Reserved => unreachable!("the key was checked"),
+            }
+        }))
+    }
+
+    /// Return true if there are no values stored in the slab.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return a number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return an iterator over the slab.
+    pub fn iter(&self) -> RawIter<'_, V> {
+        RawIter {
+            entries: self.entries.iter().enumerate(),
+            len: self.len,
+        }
+    }
+
+    /// Return an iterator that allows modifying each value.
+    pub fn iter_mut(&mut self) -> RawIterMut<'_, V> {
+        RawIterMut {
+            entries: self.entries.iter_mut().enumerate(),
+            len: self.len,
+        }
+    }
+
+    /// Return a draining iterator that removes all elements from the slab
+    /// and yields the removed items.
+    pub fn drain(&mut self) -> RawDrain<'_, V> {
+        self.reset_free_list();
+        RawDrain {
+            entries: self.entries.drain(..),
+            len: mem::take(&mut self.len),
+        }
+    }
+
+    /// Move values from the end of the slab to the vacant entries
+    /// at the beginning of it and release the memory at the end.
+    ///
+    /// The closure is called with every value before it's moved,
+    /// and the old and the new key of it. If the closure returns false,
+    /// then the value is not moved and the compaction stops.
+    /// Reserved entries are never moved, and the compaction stops
+    /// at the last of them.
+    pub fn compact<F>(&mut self, mut rekey: F)
+    where
+        F: FnMut(&mut V, usize, usize) -> bool,
+    {
+        self.release_dropped();
+        let mut to = 0;
+        loop {
+            self.release_vacant_tail();
+            while let Some(Slot::Occupied(_) | Slot::Reserved) = self.entries.get(to) {
+                to += 1;
+            }
+            if to >= self.entries.len() {
+                break;
+            }
+            let from = self.entries.len() - 1;
+            let moved = match self.entries.last_mut() {
+                Some(Slot::Occupied(value)) => rekey(value, from, to),
+                _ => false,
+            };
+            if !moved {
+                break;
+            }
+            if let Some(slot) = self.entries.pop() {
+                self.unlink(to);
+                self.entries[to] = slot;
+            }
+        }
+        self.entries.shrink_to_fit();
+    }
+
+    /// Retain only the values for which the predicate returns true.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize, &mut V) -> bool,
+    {
+        for key in 0..self.entries.len() {
+            let keep = match &mut self.entries[key] {
+                Slot::Occupied(value) => f(key, value),
+                Slot::Vacant { .. } | Slot::Reserved => true,
+            };
+            if !keep {
+                self.try_remove(key);
+            }
+        }
+    }
+
+    /// Store the value in the vacant entry of the key or right after
+    /// the end of the slab.
+    fn occupy(&mut self, key: usize, value: V) -> &mut V {
+        self.place(key, Slot::Occupied(value));
+        self.len += 1;
+        match &mut self.entries[key] {
+            Slot::Occupied(value) => value,
+            Slot::Vacant { .. } | Slot::Reserved => unreachable!("the value was inserted"),
+        }
+    }
+
+    /// Put the slot to the vacant entry of the key or right after
+    /// the end of the slab.
+    fn place(&mut self, key: usize, slot: Slot<V>) {
+        if key == self.entries.len() {
+            self.entries.push(slot);
+        } else {
+            self.unlink(key);
+            self.entries[key] = slot;
+        }
+        if let Reuse::Quarantine(quarantine) = &mut self.reuse {
+            quarantine.inserts += 1;
+        }
+    }
+
+    /// Return the number of vacant entries that can be reused
+    /// without waiting for the quarantine.
+    fn reusable(&self) -> usize {
+        let vacant = self.entries.len() - self.len - self.reserved;
+        match &self.reuse {
+            Reuse::Quarantine(quarantine) => vacant - quarantine.held.len(),
+            _ => vacant,
+        }
+    }
+
+    /// Extend the slab with vacant entries up to the key, so the lowest
+    /// of the new entries is reused first.
+    fn grow(&mut self, key: usize) -> Result<(), InsertAtError> {
+        let start = self.entries.len();
+        let additional = (key - start)
+            .checked_add(1)
+            .ok_or(InsertAtError::CapacityOverflow)?;
+        self.entries
+            .try_reserve(additional)
+            .map_err(|_| InsertAtError::CapacityOverflow)?;
+        self.entries.resize_with(key, || Slot::UNLINKED);
+        match self.reuse {
+            Reuse::Lifo | Reuse::Quarantine(_) => {
+                (start..key).rev().for_each(|idx| self.push_front(idx));
+            }
+            Reuse::Fifo { .. } => (start..key).for_each(|idx| self.push_back(idx)),
+            Reuse::LowestFirst(_) => (start..key).for_each(|idx| self.link(idx)),
+        }
+        Ok(())
+    }
+
+    /// Add the released entry to the free list by the policy.
+    fn link(&mut self, key: usize) {
+        match &mut self.reuse {
+            Reuse::Lifo => self.push_front(key),
+            Reuse::Fifo { .. } => self.push_back(key),
+            Reuse::LowestFirst(lowest) => {
+                lowest.insert(key);
+                self.entries[key] = Slot::UNLINKED;
+            }
+            Reuse::Quarantine(quarantine) => {
+                let until = quarantine.inserts.saturating_add(quarantine.delay);
+                quarantine.held.push_back((key, until));
+                self.entries[key] = Slot::UNLINKED;
+            }
+        }
+    }
+
+    fn push_front(&mut self, key: usize) {
+        let next = self.head;
+        match self.entries.get_mut(next) {
+            Some(Slot::Vacant { prev, .. }) => *prev = key,
+            _ => self.set_tail(key),
+        }
+        self.entries[key] = Slot::Vacant { prev: NIL, next };
+        self.head = key;
+    }
+
+    fn push_back(&mut self, key: usize) {
+        let prev = match self.reuse {
+            Reuse::Fifo { tail } => tail,
+            _ => NIL,
+        };
+        match self.entries.get_mut(prev) {
+            Some(Slot::Vacant { next, .. }) => *next = key,
+            _ => self.head = key,
+        }
+        self.entries[key] = Slot::Vacant { prev, next: NIL };
+        self.set_tail(key);
+    }
+
+    /// Only the FIFO policy appends to the free list, so others
+    /// don't track the tail of it.
+    fn set_tail(&mut self, key: usize) {
+        if let Reuse::Fifo { tail } = &mut self.reuse {
+            *tail = key;
+        }
+    }
+
+    /// Remove the vacant entry from the free list or the policy.
+    fn unlink(&mut self, key: usize) {
+        let Slot::Vacant { prev, next } = self.entries[key] else {
+            return;
+        };
+        if prev != NIL || next != NIL || self.head == key {
+            match self.entries.get_mut(prev) {
+                Some(Slot::Vacant { next: link, .. }) => *link = next,
+                _ => self.head = next,
+            }
+            match self.entries.get_mut(next) {
+                Some(Slot::Vacant { prev: link, .. }) => *link = prev,
+                _ => self.set_tail(prev),
+            }
+            return;
+        }
+        match &mut self.reuse {
+            Reuse::LowestFirst(lowest) => {
+                lowest.remove(&key);
+            }
+            Reuse::Quarantine(quarantine) => {
+                let held = &mut quarantine.held;
+                if let Some(pos) = held.iter().position(|&(held, _)| held == key) {
+                    held.remove(pos);
+                }
+            }
+            Reuse::Lifo | Reuse::Fifo { .. } => {}
+        }
+    }
+
+    fn reset_free_list(&mut self) {
+        self.head = NIL;
+        match &mut self.reuse {
+            Reuse::Lifo => {}
+            Reuse::Fifo { tail } => *tail = NIL,
+            Reuse::LowestFirst(lowest) => lowest.clear(),
+            Reuse::Quarantine(quarantine) => quarantine.held.clear(),
+        }
+        self.reserved = 0;
+        #[cfg(target_has_atomic = "ptr")]
+        {
+            self.dropped = None;
+        }
+    }
+
+    #[cfg(target_has_atomic = "ptr")]
+    fn take_reservation<K>(&mut self, mut reserved: Reserved<K>) -> usize {
+        let key = reserved.idx;
+        let owned = match (&self.dropped, &reserved.queue) {
+            (Some(own), Some(queue)) => Arc::ptr_eq(own, queue),
+            _ => false,
+        };
+        assert!(
+            owned && matches!(self.entries.get(key), Some(Slot::Reserved)),
+            "the reservation doesn't belong to the slab"
+        );
+        reserved.queue = None;
+        self.reserved -= 1;
+        key
+    }
+
+    /// Return the keys of dropped reservations to the free list.
+    fn release_dropped(&mut self) {
+        #[cfg(target_has_atomic = "ptr")]
+        if let Some(queue) = self.dropped.take() {
+            queue.with_keys(|keys| {
+                for key in keys.drain(..) {
+                    self.reserved -= 1;
+                    self.link(key);
+                }
+            });
+            self.dropped = Some(queue);
+        }
+    }
+
+    fn release_vacant_tail(&mut self) {
+        while let Some(Slot::Vacant { .. }) = self.entries.last() {
+            self.unlink(self.entries.len() - 1);
+            self.entries.pop();
+        }
+    }
+}
+
+impl<V> fmt::Debug for RawSlab<V>
+where
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V> Index<usize> for RawSlab<V> {
+    type Output = V;
+
+    /// # Panics
+    ///
+    /// Panics if the key is not associated with a value.
+    ///
+    fn index(&self, key: usize) -> &V {
+        self.get(key).expect("invalid key")
+    }
+}
+
+impl<V> IndexMut<usize> for RawSlab<V> {
+    /// # Panics
+    ///
+    /// Panics if the key is not associated with a value.
+    ///
+    fn index_mut(&mut self, key: usize) -> &mut V {
+        self.get_mut(key).expect("invalid key")
+    }
+}
+
+impl<V> IntoIterator for RawSlab<V> {
+    type Item = (usize, V);
+    type IntoIter = RawIntoIter<V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RawIntoIter {
+            entries: self.entries.into_iter().enumerate(),
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a RawSlab<V> {
+    type Item = (usize, &'a V);
+    type IntoIter = RawIter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a mut RawSlab<V> {
+    type Item = (usize, &'a mut V);
+    type IntoIter = RawIterMut<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// The error returned by [`RawSlab::insert_at`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertAtError {
+    /// The storage can't grow to contain the key.
+    CapacityOverflow,
+    /// The key is reserved.
+    Reserved,
+}
+
+impl fmt::Display for InsertAtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CapacityOverflow => write!(f, "the slab can't grow to contain the key"),
+            Self::Reserved => write!(f, "the key is reserved"),
+        }
+    }
+}
+
+impl core::error::Error for InsertAtError {}
+
+/// Keys of dropped reservations that are waiting to be returned
+/// to the free list of the slab.
+///
+/// Without `std`, the keys are guarded by a spinlock.
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Debug, Default)]
+struct DropQueue {
+    #[cfg(feature = "std")]
+    keys: std::sync::Mutex<Vec<usize>>,
+    #[cfg(not(feature = "std"))]
+    locked: AtomicBool,
+    #[cfg(not(feature = "std"))]
+    keys: UnsafeCell<Vec<usize>>,
+}
+
+// SAFETY: the keys are only accessed while the lock is held.
+#[cfg(all(target_has_atomic = "ptr", not(feature = "std")))]
+unsafe impl Sync for DropQueue {}
+
+/// The lock is released on unwind, and pushing or draining keys
+/// can't leave them half-updated.
+#[cfg(target_has_atomic = "ptr")]
+impl RefUnwindSafe for DropQueue {}
+
+#[cfg(all(target_has_atomic = "ptr", feature = "std"))]
+impl DropQueue {
+    fn with_keys<R>(&self, f: impl FnOnce(&mut Vec<usize>) -> R) -> R {
+        let mut keys = self
+            .keys
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        f(&mut keys)
+    }
+}
+
+#[cfg(all(target_has_atomic = "ptr", not(feature = "std")))]
+impl DropQueue {
+    fn with_keys<R>(&self, f: impl FnOnce(&mut Vec<usize>) -> R) -> R {
+        struct Unlock<'a>(&'a AtomicBool);
+
+        impl Drop for Unlock<'_> {
+            fn drop(&mut self) {
+                self.0.store(false, Ordering::Release);
+            }
+        }
+
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        let _unlock = Unlock(&self.locked);
+        // SAFETY: the lock is held, so the keys are not accessed elsewhere.
+        f(unsafe { &mut *self.keys.get() })
+    }
+}
+
+/// A key taken out of the free list of a slab before the value
+/// associated with it is constructed.
+///
+/// Created by [`RawSlab::reserve_key`] and [`TypedSlab::reserve_key`].
+/// If dropped, the key is returned to the free list on the next
+/// modification of the slab. Reservations are only available
+/// on targets with atomic pointers.
+///
+/// [`TypedSlab::reserve_key`]: crate::TypedSlab::reserve_key
+#[cfg(target_has_atomic = "ptr")]
+pub struct Reserved<K> {
+    idx: usize,
+    /// Taken when the reservation is filled or cancelled.
+    queue: Option<Arc<DropQueue>>,
+    _key: PhantomData<fn() -> K>,
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<K> Reserved<K> {
+    pub(crate) fn retype<T>(mut self) -> Reserved<T> {
+        Reserved {
+            idx: self.idx,
+            queue: self.queue.take(),
+            _key: PhantomData,
+        }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<K> Drop for Reserved<K> {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.with_keys(|keys| keys.push(self.idx));
+        }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<K> Reserved<K>
+where
+    K: SlabKey,
+{
+    /// Return the reserved key.
+    pub fn key(&self) -> K {
+        K::from_index(self.idx)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<K> fmt::Debug for Reserved<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Reserved").field(&self.idx).finish()
+    }
+}
+
+/// A handle to a vacant entry in a `RawSlab`.
+#[derive(Debug)]
+pub struct RawVacantEntry<'a, V> {
+    slab: &'a mut RawSlab<V>,
+    key: usize,
+}
+
+impl<'a, V> RawVacantEntry<'a, V> {
+    /// Return the key that will be associated with the inserted value.
+    pub fn key(&self) -> usize {
+        self.key
+    }
+
+    /// Insert a value in the entry, returning a reference to the stored value.
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.slab.occupy(self.key, value)
+    }
+}
+
+/// Parallel iterators split the entries between threads and skip
+/// the vacant ones, so they don't know the number of values.
+#[cfg(feature = "rayon")]
+impl<V> RawSlab<V> {
+    pub(crate) fn par_iter(&self) -> impl ParallelIterator<Item = (usize, &V)>
+    where
+        V: Sync,
+    {
+        self.entries.par_iter().enumerate().filter_map(occupied)
+    }
+
+    pub(crate) fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (usize, &mut V)>
+    where
+        V: Send,
+    {
+        self.entries
+            .par_iter_mut()
+            .enumerate()
+            .filter_map(occupied_mut)
+    }
+
+    pub(crate) fn par_drain(&mut self) -> impl ParallelIterator<Item = (usize, V)> + '_
+    where
+        V: Send,
+    {
+        self.reset_free_list();
+        self.len = 0;
+        self.entries
+            .par_drain(..)
+            .enumerate()
+            .filter_map(|(idx, slot)| Some((idx, take(slot)?)))
+    }
+}
+
+fn occupied<V>((idx, slot): (usize, &Slot<V>)) -> Option<(usize, &V)> {
+    match slot {
+        Slot::Occupied(value) => Some((idx, value)),
+        Slot::Vacant { .. } | Slot::Reserved => None,
+    }
+}
+
+fn occupied_mut<V>((idx, slot): (usize, &mut Slot<V>)) -> Option<(usize, &mut V)> {
+    match slot {
+        Slot::Occupied(value) => Some((idx, value)),
+        Slot::Vacant { .. } | Slot::Reserved => None,
+    }
+}
+
+fn take<V>(slot: Slot<V>) -> Option<V> {
+    match slot {
+        Slot::Occupied(value) => Some(value),
+        Slot::Vacant { .. } | Slot::Reserved => None,
+    }
+}
+
+/// An iterator over the keys and values of a `RawSlab`.
+#[derive(Debug)]
+pub struct RawIter<'a, V> {
+    entries: Enumerate<slice::Iter<'a, Slot<V>>>,
+    len: usize,
+}
+
+impl<V> Clone for RawIter<'_, V> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, V> Iterator for RawIter<'a, V> {
+    type Item = (usize, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.entries.by_ref().find_map(occupied)?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<V> DoubleEndedIterator for RawIter<'_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let item = self.entries.by_ref().rev().find_map(occupied)?;
+        self.len -= 1;
+        Some(item)
+    }
+}
+
+impl<V> ExactSizeIterator for RawIter<'_, V> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<V> FusedIterator for RawIter<'_, V> {}
+
+/// A mutable iterator over the keys and values of a `RawSlab`.
+#[derive(Debug)]
+pub struct RawIterMut<'a, V> {
+    entries: Enumerate<slice::IterMut<'a, Slot<V>>>,
+    len: usize,
+}
+
+impl<'a, V> Iterator for RawIterMut<'a, V> {
+    type Item = (usize, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.entries.by_ref().find_map(occupied_mut)?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<V> DoubleEndedIterator for RawIterMut<'_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let item = self.entries.by_ref().rev().find_map(occupied_mut)?;
+        self.len -= 1;
+        Some(item)
+    }
+}
+
+impl<V> ExactSizeIterator for RawIterMut<'_, V> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<V> FusedIterator for RawIterMut<'_, V> {}
+
+/// An owning iterator over the keys and values of a `RawSlab`.
+#[derive(Debug)]
+pub struct RawIntoIter<V> {
+    entries: Enumerate<vec::IntoIter<Slot<V>>>,
+    len: usize,
+}
+
+impl<V> Iterator for RawIntoIter<V> {
+    type Item = (usize, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self
+            .entries
+            .by_ref()
+            .find_map(|(idx, slot)| Some((idx, take(slot)?)))?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<V> DoubleEndedIterator for RawIntoIter<V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let item = self
+            .entries
+            .by_ref()
+            .rev()
+            .find_map(|(idx, slot)| Some((idx, take(slot)?)))?;
+        self.len -= 1;
+        Some(item)
+    }
+}
+
+impl<V> ExactSizeIterator for RawIntoIter<V> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<V> FusedIterator for RawIntoIter<V> {}
+
+/// A draining iterator over the values of a `RawSlab`.
+#[derive(Debug)]
+pub struct RawDrain<'a, V> {
+    entries: vec::Drain<'a, Slot<V>>,
+    len: usize,
+}
+
+impl<V> Iterator for RawDrain<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        let value = self.entries.by_ref().find_map(take)?;
+        self.len -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<V> DoubleEndedIterator for RawDrain<'_, V> {
+    fn next_back(&mut self) -> Option<V> {
+        let value = self.entries.by_ref().rev().find_map(take)?;
+        self.len -= 1;
+        Some(value)
+    }
+}
+
+impl<V> ExactSizeIterator for RawDrain<'_, V> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<V> FusedIterator for RawDrain<'_, V> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(target_has_atomic = "ptr")]
+    use std::collections::BTreeMap;
+
+    #[cfg(target_has_atomic = "ptr")]
+    struct XorShift(u64);
+
+    #[cfg(target_has_atomic = "ptr")]
+    impl XorShift {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    /// Check that every vacant entry is reachable exactly once
+    /// from the free list or the keys held by the policy.
+    fn check_free_list<V>(slab: &RawSlab<V>) {
+        let mut linked = BTreeSet::new();
+        let (mut prev, mut key) = (NIL, slab.head);
+        while key != NIL {
+            let Slot::Vacant { prev: link, next } = slab.entries[key] else {
+                panic!("the entry {key} isn't vacant");
+            };
+            assert_eq!(link, prev);
+            assert!(linked.insert(key));
+            (prev, key) = (key, next);
+        }
+        let held: Vec<usize> = match &slab.reuse {
+            Reuse::Lifo => Vec::new(),
+            Reuse::Fifo { tail } => {
+                assert_eq!(*tail, prev);
+                Vec::new()
+            }
+            Reuse::LowestFirst(lowest) => lowest.iter().copied().collect(),
+            Reuse::Quarantine(quarantine) => quarantine.held.iter().map(|&(key, _)| key).collect(),
+        };
+        for key in held {
+            assert!(matches!(
+                slab.entries[key],
+                Slot::Vacant {
+                    prev: NIL,
+                    next: NIL
+                }
+            ));
+            assert!(linked.insert(key));
+        }
+        let vacant = slab.entries.iter().enumerate();
+        let vacant: BTreeSet<usize> = vacant
+            .filter_map(|(key, slot)| matches!(slot, Slot::Vacant { .. }).then_some(key))
+            .collect();
+        assert_eq!(linked, vacant);
+        let reserved = slab
+            .entries
+            .iter()
+            .filter(|slot| matches!(slot, Slot::Reserved));
+        assert_eq!(reserved.count(), slab.reserved);
+        assert_eq!(slab.len + slab.reserved + vacant.len(), slab.entries.len());
+    }
+
+    #[test]
+    fn test_free_list() {
+        let mut slab: RawSlab<usize> = RawSlab::new();
+        assert_eq!(slab.insert_at(4, 4), Ok(None));
+        assert_eq!(slab.insert_at(1, 1), Ok(None));
+        assert_eq!(slab.insert_at(4, 40), Ok(Some(4)));
+        assert_eq!(slab.insert(0), 0);
+        assert_eq!(slab.insert(2), 2);
+        slab.remove(1);
+        assert_eq!(slab.insert(1), 1);
+        assert_eq!(slab.insert(3), 3);
+        assert_eq!(slab.insert(5), 5);
+        assert_eq!(slab.iter().len(), 6);
+        slab.retain(|key, _| key % 2 == 0);
+        slab.compact(|_, from, to| from == 4 && to == 1);
+        assert_eq!(
+            slab.iter().collect::<Vec<_>>(),
+            [(0, &0), (1, &40), (2, &2)]
+        );
+        assert_eq!(slab.insert(3), 3);
+        check_free_list(&slab);
+    }
+
+    #[test]
+    fn test_unwind_safe() {
+        use core::panic::{RefUnwindSafe, UnwindSafe};
+
+        fn assert_unwind_safe<T: UnwindSafe + RefUnwindSafe>() {}
+        assert_unwind_safe::<RawSlab<usize>>();
+        assert_unwind_safe::<crate::TypedSlab<usize, usize>>();
+        #[cfg(target_has_atomic = "ptr")]
+        assert_unwind_safe::<Reserved<usize>>();
+    }
+
+    #[test]
+    #[cfg(target_has_atomic = "ptr")]
+    fn test_dropped_reservation() {
+        let mut slab: RawSlab<usize> = RawSlab::new();
+        slab.insert(0);
+        let reserved = slab.reserve_key();
+        let mut copy = slab.clone();
+        std::thread::spawn(move || drop(reserved)).join().unwrap();
+        assert_eq!(slab.vacant_key(), 2);
+        assert_eq!(slab.insert(2), 2);
+        assert_eq!(slab.insert(1), 1);
+        assert_eq!(copy.insert(1), 1);
+    }
+
+    #[test]
+    #[cfg(target_has_atomic = "ptr")]
+    fn test_against_model() {
+        let policies = [
+            ReusePolicy::Lifo,
+            ReusePolicy::Fifo,
+            ReusePolicy::LowestFirst,
+            ReusePolicy::Quarantine(0),
+            ReusePolicy::Quarantine(3),
+        ];
+        for (seed, policy) in (1..).zip(policies) {
+            let mut rng = XorShift(seed);
+            let mut slab = RawSlab::with_policy(policy);
+            let mut model = BTreeMap::new();
+            let mut reservations = Vec::new();
+            // The number of assigned keys when a key was released.
+            let mut released = BTreeMap::new();
+            let mut assigned: usize = 0;
+            for value in 0..3000 {
+                let expected = slab.vacant_key();
+                match policy {
+                    ReusePolicy::LowestFirst => {
+                        let mut vacant = slab.entries.iter();
+                        let lowest = vacant.position(|slot| matches!(slot, Slot::Vacant { .. }));
+                        assert_eq!(expected, lowest.unwrap_or(slab.entries.len()));
+                    }
+                    ReusePolicy::Quarantine(delay) => {
+                        if let Some(at) = released.get(&expected) {
+                            assert!(assigned - at >= delay);
+                        }
+                    }
+                    ReusePolicy::Lifo | ReusePolicy::Fifo => {}
+                }
+                let key = rng.below(slab.entries.len() + 3);
+                match rng.below(20) {
+                    0..=5 => {
+                        let key = if value % 2 == 0 {
+                            slab.insert(value)
+                        } else {
+                            let entry = slab.vacant_entry();
+                            let key = entry.key();
+                            entry.insert(value);
+                            key
+                        };
+                        assert_eq!(key, expected);
+                        model.insert(key, value);
+                        released.remove(&key);
+                        assigned += 1;
+                    }
+                    6..=7 => {
+                        let result = slab.insert_at(key, value);
+                        if reservations.iter().any(|r: &Reserved<usize>| r.idx == key) {
+                            assert_eq!(result, Err(InsertAtError::Reserved));
+                        } else {
+                            let old = model.insert(key, value);
+                            assert_eq!(result, Ok(old));
+                            if old.is_none() {
+                                released.remove(&key);
+                                assigned += 1;
+                            }
+                        }
+                    }
+                    8..=11 => {
+                        let removed = model.remove(&key);
+                        assert_eq!(slab.try_remove(key), removed);
+                        if removed.is_some() {
+                            released.insert(key, assigned);
+                        }
+                    }
+                    12..=13 => {
+                        let reserved = slab.reserve_key();
+                        assert_eq!(reserved.idx, expected);
+                        released.remove(&expected);
+                        assigned += 1;
+                        reservations.push(reserved);
+                    }
+                    14..=15 if !reservations.is_empty() => {
+                        let reserved = reservations.swap_remove(rng.below(reservations.len()));
+                        let key = reserved.idx;
+                        match rng.below(3) {
+                            0 => {
+                                model.insert(slab.fill(reserved, value), value);
+                            }
+                            1 => {
+                                slab.cancel(reserved);
+                                released.insert(key, assigned);
+                            }
+                            _ => {
+                                drop(reserved);
+                                released.insert(key, assigned);
+                            }
+                        }
+                    }
+                    16 => {
+                        slab.retain(|key, value| (key + *value) % 5 != 0);
+                        model.retain(|key, value| {
+                            let keep = (key + *value) % 5 != 0;
+                            if !keep {
+                                released.insert(*key, assigned);
+                            }
+                            keep
+                        });
+                    }
+                    17 => slab.compact(|_, from, to| {
+                        let value = model.remove(&from).unwrap();
+                        model.insert(to, value);
+                        released.remove(&to);
+                        true
+                    }),
+                    18 => {
+                        let copy = slab.clone();
+                        check_free_list(&copy);
+                        assert_eq!(copy.reserved, 0);
+                        assert!(copy.iter().eq(slab.iter()));
+                        slab.shrink_to_fit();
+                    }
+                    19 if rng.below(10) == 0 => {
+                        assert!(slab.drain().eq(model.into_values()));
+                        model = BTreeMap::new();
+                        reservations.clear();
+                        released.clear();
+                    }
+                    _ => {}
+                }
+                // Vacant entries at the end are released by shrinking.
+                released.retain(|key, _| *key < slab.entries.len());
+                assert_eq!(slab.len(), model.len());
+                assert!(slab
+                    .iter()
+                    .eq(model.iter().map(|(key, value)| (*key, value))));
+                check_free_list(&slab);
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Parallel iterators over typed slabs.
+
+use crate::{SlabKey, TypedSlab};
+use rayon::iter::ParallelIterator;
+
+/// Parallel iterators split the entries of the slab between threads
+/// and skip the vacant ones, so they are not indexed.
+impl<K, V> TypedSlab<K, V>
+where
+    K: SlabKey + Send,
+{
+    /// Return a parallel iterator over the slab.
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (K, &V)>
+    where
+        V: Sync,
+    {
+        self.slab.par_iter().map(|(idx, v)| (K::from_index(idx), v))
+    }
+
+    /// Return a parallel iterator that allows modifying each value.
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (K, &mut V)>
+    where
+        V: Send,
+    {
+        self.slab
+            .par_iter_mut()
+            .map(|(idx, v)| (K::from_index(idx), v))
+    }
+
+    /// Return a parallel iterator with references to values.
+    pub fn par_values(&self) -> impl ParallelIterator<Item = &V>
+    where
+        V: Sync,
+    {
+        self.slab.par_iter().map(|(_, v)| v)
+    }
+
+    /// Remove all elements from the slab and return a parallel iterator
+    /// over the removed keys and values.
+    pub fn par_drain(&mut self) -> impl ParallelIterator<Item = (K, V)> + '_
+    where
+        V: Send,
+    {
+        self.slab
+            .par_drain()
+            .map(|(idx, v)| (K::from_index(idx), v))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_par_iter() {
+        let mut slab: TypedSlab<usize, usize> = (0..1000).collect();
+        slab.remove(10);
+        slab.par_iter_mut().for_each(|(key, value)| *value += key);
+        assert!(slab.par_iter().all(|(key, value)| *value == key * 2));
+        assert_eq!(
+            slab.par_values().sum::<usize>(),
+            (0..1000).sum::<usize>() * 2 - 20
+        );
+        let drained: Vec<_> = slab.par_drain().collect();
+        assert_eq!(drained[10], (11, 22));
+        assert!(slab.is_empty());
+    }
+}
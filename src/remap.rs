@@ -0,0 +1,64 @@
+//! Reports of keys moved by compaction.
+
+use crate::SlabKey;
+use alloc::collections::BTreeMap;
+use core::marker::PhantomData;
+
+/// A table of keys moved by [`TypedSlab::compact_with_remap`](crate::TypedSlab::compact_with_remap)
+/// that can be applied to other structures holding the keys.
+#[derive(Debug, Clone)]
+pub struct KeyRemap<K> {
+    moved: BTreeMap<usize, usize>,
+    _key: PhantomData<K>,
+}
+
+impl<K> Default for KeyRemap<K> {
+    fn default() -> Self {
+        Self {
+            moved: BTreeMap::new(),
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K> KeyRemap<K>
+where
+    K: SlabKey,
+{
+    pub(crate) fn insert(&mut self, old: usize, new: usize) {
+        self.moved.insert(old, new);
+    }
+
+    /// Return the new key of a moved value.
+    /// If the value with the given key was not moved, then `None` is returned.
+    pub fn get(&self, old: K) -> Option<K> {
+        let idx = self.moved.get(&old.index())?;
+        Some(K::from_index(*idx))
+    }
+
+    /// Return the actual key for the given key, that is the new key
+    /// if the value was moved, or the same key otherwise.
+    pub fn remap(&self, key: K) -> K {
+        match self.moved.get(&key.index()) {
+            Some(idx) => K::from_index(*idx),
+            None => key,
+        }
+    }
+
+    /// Return true if no keys were moved.
+    pub fn is_empty(&self) -> bool {
+        self.moved.is_empty()
+    }
+
+    /// Return a number of moved keys.
+    pub fn len(&self) -> usize {
+        self.moved.len()
+    }
+
+    /// Return an iterator over pairs of old and new keys.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (K, K)> + '_ {
+        self.moved
+            .iter()
+            .map(|(old, new)| (K::from_index(*old), K::from_index(*new)))
+    }
+}
@@ -0,0 +1,246 @@
+//! Maps that attach extra data to the keys of a slab.
+
+use crate::{KeyRemap, SlabKey, TypedSlab};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// A map from the keys of a [`TypedSlab`] to values stored densely
+/// in a vector indexed by the keys.
+///
+/// Fits best for data that is attached to most of the slab entries.
+#[derive(Debug, Clone)]
+pub struct SecondaryMap<K, X> {
+    values: Vec<Option<X>>,
+    len: usize,
+    _key: PhantomData<K>,
+}
+
+impl<K, X> Default for SecondaryMap<K, X> {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            len: 0,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K, X> SecondaryMap<K, X>
+where
+    K: SlabKey,
+{
+    /// Construct a new, empty `SecondaryMap`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value for the key, returning the previous value if any.
+    pub fn insert(&mut self, key: K, value: X) -> Option<X> {
+        let idx = key.index();
+        if idx >= self.values.len() {
+            self.values.resize_with(idx + 1, || None);
+        }
+        let prev = self.values[idx].replace(value);
+        if prev.is_none() {
+            self.len += 1;
+        }
+        prev
+    }
+
+    /// Remove and return the value associated with the given key.
+    pub fn remove(&mut self, key: K) -> Option<X> {
+        let value = self.values.get_mut(key.index())?.take();
+        if value.is_some() {
+            self.len -= 1;
+        }
+        value
+    }
+
+    /// Return a reference to the value associated with the given key.
+    pub fn get(&self, key: K) -> Option<&X> {
+        self.values.get(key.index())?.as_ref()
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    pub fn get_mut(&mut self, key: K) -> Option<&mut X> {
+        self.values.get_mut(key.index())?.as_mut()
+    }
+
+    /// Return true if a value is associated with the given key.
+    pub fn contains_key(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Return true if there are no values stored in the map.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return a number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Remove all values from the map.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.len = 0;
+    }
+
+    /// Return an iterator over the map ordered by keys.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (K, &X)> {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, v)| Some((K::from_index(idx), v.as_ref()?)))
+    }
+
+    /// Return an iterator that allows modifying each value.
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = (K, &mut X)> {
+        self.values
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(idx, v)| Some((K::from_index(idx), v.as_mut()?)))
+    }
+
+    /// Remove values whose keys are not associated with values
+    /// of the primary slab.
+    pub fn purge<V>(&mut self, slab: &TypedSlab<K, V>) {
+        for (idx, value) in self.values.iter_mut().enumerate() {
+            if value.is_some() && !slab.slab.contains(idx) {
+                *value = None;
+                self.len -= 1;
+            }
+        }
+    }
+
+    /// Move values to the new keys reported by the compaction of the primary slab.
+    pub fn remap_keys(&mut self, remap: &KeyRemap<K>) {
+        for (old, new) in remap.iter() {
+            match self.remove(old) {
+                Some(value) => self.insert(new, value),
+                None => self.remove(new),
+            };
+        }
+    }
+}
+
+/// A map from the keys of a [`TypedSlab`] to values stored in a tree.
+///
+/// Fits best for data that is attached to a few of the slab entries.
+#[derive(Debug, Clone)]
+pub struct SparseSecondaryMap<K, X> {
+    values: BTreeMap<usize, X>,
+    _key: PhantomData<K>,
+}
+
+impl<K, X> Default for SparseSecondaryMap<K, X> {
+    fn default() -> Self {
+        Self {
+            values: BTreeMap::new(),
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K, X> SparseSecondaryMap<K, X>
+where
+    K: SlabKey,
+{
+    /// Construct a new, empty `SparseSecondaryMap`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value for the key, returning the previous value if any.
+    pub fn insert(&mut self, key: K, value: X) -> Option<X> {
+        self.values.insert(key.index(), value)
+    }
+
+    /// Remove and return the value associated with the given key.
+    pub fn remove(&mut self, key: K) -> Option<X> {
+        self.values.remove(&key.index())
+    }
+
+    /// Return a reference to the value associated with the given key.
+    pub fn get(&self, key: K) -> Option<&X> {
+        self.values.get(&key.index())
+    }
+
+    /// Return a mutable reference to the value associated with the given key.
+    pub fn get_mut(&mut self, key: K) -> Option<&mut X> {
+        self.values.get_mut(&key.index())
+    }
+
+    /// Return true if a value is associated with the given key.
+    pub fn contains_key(&self, key: K) -> bool {
+        self.values.contains_key(&key.index())
+    }
+
+    /// Return true if there are no values stored in the map.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Return a number of stored values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Remove all values from the map.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Return an iterator over the map ordered by keys.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (K, &X)> {
+        self.values.iter().map(|(idx, v)| (K::from_index(*idx), v))
+    }
+
+    /// Return an iterator that allows modifying each value.
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = (K, &mut X)> {
+        self.values
+            .iter_mut()
+            .map(|(idx, v)| (K::from_index(*idx), v))
+    }
+
+    /// Remove values whose keys are not associated with values
+    /// of the primary slab.
+    pub fn purge<V>(&mut self, slab: &TypedSlab<K, V>) {
+        self.values.retain(|idx, _| slab.slab.contains(*idx));
+    }
+
+    /// Move values to the new keys reported by the compaction of the primary slab.
+    pub fn remap_keys(&mut self, remap: &KeyRemap<K>) {
+        for (old, new) in remap.iter() {
+            match self.values.remove(&old.index()) {
+                Some(value) => self.values.insert(new.index(), value),
+                None => self.values.remove(&new.index()),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_purge() {
+        let mut slab: TypedSlab<usize, &str> = TypedSlab::new();
+        let mut dense = SecondaryMap::new();
+        let mut sparse = SparseSecondaryMap::new();
+        for value in ["a", "b", "c"] {
+            let key = slab.insert(value);
+            dense.insert(key, value.len());
+            sparse.insert(key, value.len());
+        }
+        slab.remove(1);
+        dense.purge(&slab);
+        sparse.purge(&slab);
+        assert_eq!(dense.len(), 2);
+        assert_eq!(dense.get(1), None);
+        assert_eq!(sparse.iter().map(|(k, _)| k).collect::<Vec<_>>(), [0, 2]);
+    }
+}
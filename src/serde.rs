@@ -0,0 +1,158 @@
+//! Serialization of slabs that preserves keys and vacant entries.
+
+use crate::{RawSlab, ReusePolicy, SlabKey, TypedSlab};
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::{Deserialize, Deserializer, Error, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// Serialized as a map of indices to values, so the vacant entries
+/// are restored on deserialization and keys remain the same.
+///
+/// The policy of key reuse is not serialized: slabs are deserialized
+/// with the default policy or the one given to
+/// [`RawSlab::deserialize_with_policy`].
+impl<V> Serialize for RawSlab<V>
+where
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(self.iter())
+    }
+}
+
+struct RawSlabVisitor<V> {
+    policy: ReusePolicy,
+    _value: PhantomData<V>,
+}
+
+impl<'de, V> Visitor<'de> for RawSlabVisitor<V>
+where
+    V: Deserialize<'de>,
+{
+    type Value = RawSlab<V>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a map of indices to values")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut slab = RawSlab::with_policy(self.policy);
+        while let Some((idx, value)) = map.next_entry()? {
+            slab.insert_at(idx, value).map_err(A::Error::custom)?;
+        }
+        Ok(slab)
+    }
+}
+
+impl<'de, V> Deserialize<'de> for RawSlab<V>
+where
+    V: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::deserialize_with_policy(deserializer, ReusePolicy::default())
+    }
+}
+
+impl<V> RawSlab<V> {
+    /// Deserialize a slab that reuses keys by the policy.
+    pub fn deserialize_with_policy<'de, D>(
+        deserializer: D,
+        policy: ReusePolicy,
+    ) -> Result<Self, D::Error>
+    where
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(RawSlabVisitor {
+            policy,
+            _value: PhantomData,
+        })
+    }
+}
+
+/// Serialized the same way as [`RawSlab`], without the policy of key reuse.
+impl<K, V> Serialize for TypedSlab<K, V>
+where
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.slab.serialize(serializer)
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for TypedSlab<K, V>
+where
+    K: SlabKey,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::deserialize_with_policy(deserializer, ReusePolicy::default())
+    }
+}
+
+impl<K, V> TypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    /// Deserialize a slab that reuses keys of removed values by the policy.
+    pub fn deserialize_with_policy<'de, D>(
+        deserializer: D,
+        policy: ReusePolicy,
+    ) -> Result<Self, D::Error>
+    where
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let slab = RawSlab::deserialize_with_policy(deserializer, policy)?;
+        if let Some((idx, _)) = slab.iter().next_back() {
+            K::try_from_index(idx).map_err(D::Error::custom)?;
+        }
+        Ok(Self {
+            slab,
+            _key: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut slab: TypedSlab<usize, &str> = TypedSlab::new();
+        let first = slab.insert("first");
+        let second = slab.insert("second");
+        slab.insert("third");
+        slab.remove(second);
+        let json = serde_json::to_string(&slab).unwrap();
+        let mut restored: TypedSlab<usize, String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.get(first).map(String::as_str), Some("first"));
+        assert_eq!(restored.get(second), None);
+        assert_eq!(restored.insert("fourth".into()), second);
+
+        slab.remove(first);
+        let json = serde_json::to_string(&slab).unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let mut restored: TypedSlab<usize, String> =
+            TypedSlab::deserialize_with_policy(&mut deserializer, ReusePolicy::Fifo).unwrap();
+        assert_eq!(restored.as_raw().policy(), ReusePolicy::Fifo);
+        assert_eq!(restored.insert("fourth".into()), first);
+    }
+}
@@ -0,0 +1,169 @@
+//! Keys validated once and used without further checks.
+
+use crate::{SlabKey, TypedSlab};
+use core::fmt;
+use core::marker::PhantomData;
+
+/// An invariant lifetime that brands a scope and its keys, so keys
+/// validated by one scope can't be passed to another.
+type Brand<'brand> = PhantomData<fn(&'brand ()) -> &'brand ()>;
+
+/// Exclusive access to a `TypedSlab` that validates keys.
+///
+/// Created by [`TypedSlab::scope`].
+#[derive(Debug)]
+pub struct Scope<'brand, K, V> {
+    slab: &'brand mut TypedSlab<K, V>,
+    _brand: Brand<'brand>,
+}
+
+/// A key that is proven to be associated with a value of the scope's slab.
+///
+/// The key borrows the scope, so it can't be used after the slab
+/// is modified:
+///
+/// ```compile_fail,E0502
+/// # use typed_slab::TypedSlab;
+/// let mut slab: TypedSlab<usize, &str> = TypedSlab::new();
+/// let key = slab.insert("value");
+/// slab.scope(|mut scope| {
+///     let valid = scope.validate(key).unwrap();
+///     scope.remove(key);
+///     scope.get_valid(valid);
+/// });
+/// ```
+///
+/// The key is branded by the scope, so it can't be used with another one:
+///
+/// ```compile_fail,E0521
+/// # use typed_slab::TypedSlab;
+/// let mut first: TypedSlab<usize, &str> = TypedSlab::new();
+/// let mut second: TypedSlab<usize, &str> = TypedSlab::new();
+/// let key = first.insert("first");
+/// second.insert("second");
+/// first.scope(|one| {
+///     second.scope(|two| {
+///         let valid = one.validate(key).unwrap();
+///         two.get_valid(valid);
+///     });
+/// });
+/// ```
+pub struct ValidKey<'scope, 'brand, K> {
+    idx: usize,
+    _scope: PhantomData<&'scope ()>,
+    _brand: Brand<'brand>,
+    _key: PhantomData<K>,
+}
+
+impl<K> Clone for ValidKey<'_, '_, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for ValidKey<'_, '_, K> {}
+
+impl<K> fmt::Debug for ValidKey<'_, '_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ValidKey").field(&self.idx).finish()
+    }
+}
+
+impl<K> ValidKey<'_, '_, K>
+where
+    K: SlabKey,
+{
+    /// Return the validated key.
+    pub fn key(&self) -> K {
+        K::from_index(self.idx)
+    }
+}
+
+impl<K, V> TypedSlab<K, V>
+where
+    K: SlabKey,
+{
+    /// Call the closure with a [`Scope`] that validates keys of the slab
+    /// once, so the values are accessed with validated keys without checks.
+    pub fn scope<F, R>(&mut self, f: F) -> R
+    where
+        F: for<'brand> FnOnce(Scope<'brand, K, V>) -> R,
+    {
+        f(Scope {
+            slab: self,
+            _brand: PhantomData,
+        })
+    }
+}
+
+impl<'brand, K, V> Scope<'brand, K, V>
+where
+    K: SlabKey,
+{
+    /// Return a validated key if the key is associated with a value.
+    pub fn validate(&self, key: K) -> Option<ValidKey<'_, 'brand, K>> {
+        let idx = key.index();
+        self.slab.slab.contains(idx).then_some(ValidKey {
+            idx,
+            _scope: PhantomData,
+            _brand: PhantomData,
+            _key: PhantomData,
+        })
+    }
+
+    /// Return a reference to the value associated with the validated key.
+    pub fn get_valid(&self, key: ValidKey<'_, 'brand, K>) -> &V {
+        // SAFETY: the key was validated by this scope, and the slab can't
+        // be modified while the key borrows the scope.
+        unsafe { self.slab.slab.get_unchecked(key.idx) }
+    }
+
+    /// Return a reference to the value associated with the given key.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.slab.get(key)
+    }
+
+    /// Insert a value in the slab, returning key assigned to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assigned index can't be represented by the key.
+    ///
+    pub fn insert(&mut self, value: V) -> K {
+        self.slab.insert(value)
+    }
+
+    /// Remove and return the value associated with the given key.
+    /// Keys validated before are invalidated.
+    /// If the given key is not associated with a value, then `None` is returned.
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        self.slab.remove(key)
+    }
+
+    /// Return a reference to the underlying `TypedSlab`.
+    pub fn as_typed(&self) -> &TypedSlab<K, V> {
+        self.slab
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_valid_key() {
+        let mut slab: TypedSlab<usize, &str> = TypedSlab::new();
+        let first = slab.insert("first");
+        let second = slab.insert("second");
+        let found = slab.scope(|mut scope| {
+            let valid = scope.validate(first).unwrap();
+            assert_eq!(valid.key(), first);
+            assert_eq!(scope.get_valid(valid), &"first");
+            scope.remove(second);
+            assert!(scope.validate(second).is_none());
+            scope.validate(first).map(|key| *scope.get_valid(key))
+        });
+        assert_eq!(found, Some("first"));
+    }
+}
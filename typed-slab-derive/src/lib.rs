@@ -0,0 +1,126 @@
+//! Derive macros for keys of `typed-slab`.
+
+#![warn(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Index};
+
+/// Derive `SlabKey` for a newtype that wraps an integer,
+/// e.g. `struct NodeId(u32)`.
+///
+/// Implements `SlabKey` that rejects indices overflowing the wrapped
+/// integer and maps values that aren't valid indices, e.g. negative ones,
+/// to `usize::MAX`, which is never associated with a value.
+/// Also implements `From<Key> for usize`, and `Debug`, `Display`, `PartialEq`,
+/// `Eq`, `PartialOrd`, `Ord` and `Hash` that delegate to the wrapped integer.
+#[proc_macro_derive(SlabKey)]
+pub fn derive_slab_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "SlabKey can't be derived for generic types",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "SlabKey can only be derived for structs",
+            ))
+        }
+    };
+    let field = match fields.iter().next() {
+        Some(field) if fields.len() == 1 => field,
+        _ => {
+            return Err(Error::new_spanned(
+                fields,
+                "SlabKey requires a struct with exactly one field",
+            ))
+        }
+    };
+    let ty = &field.ty;
+    let (member, construct) = match (fields, &field.ident) {
+        (Fields::Named(_), Some(ident)) => (quote!(#ident), quote!(Self { #ident: value })),
+        _ => {
+            let idx = Index::from(0);
+            (quote!(#idx), quote!(Self(value)))
+        }
+    };
+    let name_str = name.to_string();
+
+    Ok(quote! {
+        impl ::typed_slab::SlabKey for #name {
+            fn try_from_index(
+                index: usize,
+            ) -> ::core::result::Result<Self, ::typed_slab::IndexOverflow> {
+                match <#ty as ::core::convert::TryFrom<usize>>::try_from(index) {
+                    ::core::result::Result::Ok(value) => ::core::result::Result::Ok(#construct),
+                    ::core::result::Result::Err(_) => {
+                        ::core::result::Result::Err(::typed_slab::IndexOverflow { index })
+                    }
+                }
+            }
+
+            fn index(&self) -> usize {
+                <usize as ::core::convert::TryFrom<#ty>>::try_from(self.#member)
+                    .unwrap_or(usize::MAX)
+            }
+        }
+
+        impl ::core::convert::From<#name> for usize {
+            fn from(key: #name) -> usize {
+                <usize as ::core::convert::TryFrom<#ty>>::try_from(key.#member)
+                    .expect(concat!("the key ", #name_str, " overflows usize"))
+            }
+        }
+
+        impl ::core::fmt::Debug for #name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_tuple(#name_str).field(&self.#member).finish()
+            }
+        }
+
+        impl ::core::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                ::core::fmt::Display::fmt(&self.#member, f)
+            }
+        }
+
+        impl ::core::cmp::PartialEq for #name {
+            fn eq(&self, other: &Self) -> bool {
+                self.#member == other.#member
+            }
+        }
+
+        impl ::core::cmp::Eq for #name {}
+
+        impl ::core::cmp::PartialOrd for #name {
+            fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                ::core::option::Option::Some(::core::cmp::Ord::cmp(self, other))
+            }
+        }
+
+        impl ::core::cmp::Ord for #name {
+            fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                ::core::cmp::Ord::cmp(&self.#member, &other.#member)
+            }
+        }
+
+        impl ::core::hash::Hash for #name {
+            fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+                ::core::hash::Hash::hash(&self.#member, state)
+            }
+        }
+    })
+}